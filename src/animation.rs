@@ -0,0 +1,245 @@
+use std::{collections::HashMap, rc::Rc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::assets::TextureId;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ClipName {
+    Idle,
+    MoveLeft,
+    MoveRight,
+    Hurt,
+    Death,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LoopMode {
+    Once,
+    Loop,
+    /// Forwards, then backwards, then forwards again.
+    PingPong,
+}
+
+/// One picture of a clip. Frames of a sheet packed with `--grid` each need
+/// a manifest entry of their own, with a path like `resources/witch.png#3`,
+/// and only load from the atlas; single images get their motion from the
+/// transform instead.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AnimationFrame {
+    pub texture: TextureId,
+    pub duration: f64,
+    pub scale: f64,
+    pub angle: f64,
+    pub alpha: f64,
+}
+
+impl AnimationFrame {
+    pub fn new(texture: TextureId, duration: f64) -> Self {
+        Self {
+            texture,
+            duration,
+            scale: 1.0,
+            angle: 0.0,
+            alpha: 1.0,
+        }
+    }
+
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_angle(mut self, angle: f64) -> Self {
+        self.angle = angle;
+        self
+    }
+
+    pub fn with_alpha(mut self, alpha: f64) -> Self {
+        self.alpha = alpha;
+        self
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Clip {
+    frames: Vec<AnimationFrame>,
+    mode: LoopMode,
+}
+
+impl Clip {
+    pub fn new(mode: LoopMode, frames: Vec<AnimationFrame>) -> Self {
+        assert!(!frames.is_empty(), "a clip needs at least one frame");
+        Self { frames, mode }
+    }
+
+    fn duration(&self) -> f64 {
+        self.frames.iter().map(|it| it.duration).sum()
+    }
+
+    fn is_finished(&self, time: f64) -> bool {
+        self.mode == LoopMode::Once && time >= self.duration()
+    }
+
+    fn frame_at(&self, time: f64) -> &AnimationFrame {
+        let duration = self.duration();
+        let mut time = match self.mode {
+            LoopMode::Once => time.min(duration),
+            LoopMode::Loop => time % duration,
+            LoopMode::PingPong => {
+                let time = time % (duration * 2.0);
+                if time > duration {
+                    duration * 2.0 - time
+                } else {
+                    time
+                }
+            }
+        };
+        for frame in self.frames.iter() {
+            if time < frame.duration {
+                return frame;
+            }
+            time -= frame.duration;
+        }
+        self.frames.last().unwrap()
+    }
+}
+
+/// Clips a character can play. Missing clips fall back to `Idle`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AnimationSet {
+    clips: HashMap<ClipName, Clip>,
+}
+
+impl AnimationSet {
+    pub fn new(idle: Clip) -> Self {
+        Self {
+            clips: HashMap::from([(ClipName::Idle, idle)]),
+        }
+    }
+
+    pub fn with(mut self, name: ClipName, clip: Clip) -> Self {
+        self.clips.insert(name, clip);
+        self
+    }
+
+    /// Animates a single image: a slow breath when idle, leaning into turns,
+    /// flickering when hit and shrinking away when killed.
+    pub fn for_sprite(texture: TextureId) -> Self {
+        let frame = |duration| AnimationFrame::new(texture, duration);
+        let lean = |angle| Clip::new(LoopMode::Loop, vec![frame(1.0).with_angle(angle)]);
+        Self::new(Clip::new(
+            LoopMode::PingPong,
+            vec![frame(0.4), frame(0.4).with_scale(1.04)],
+        ))
+        .with(ClipName::MoveLeft, lean(-0.15))
+        .with(ClipName::MoveRight, lean(0.15))
+        .with(
+            ClipName::Hurt,
+            Clip::new(
+                LoopMode::Once,
+                vec![
+                    frame(0.05).with_alpha(0.4),
+                    frame(0.05),
+                    frame(0.05).with_alpha(0.4),
+                ],
+            ),
+        )
+        .with(
+            ClipName::Death,
+            Clip::new(
+                LoopMode::Once,
+                (1..=8)
+                    .map(|i| {
+                        let left = 1.0 - i as f64 / 8.0;
+                        frame(0.05)
+                            .with_scale(1.0 + (1.0 - left) * 0.5)
+                            .with_alpha(left)
+                    })
+                    .collect(),
+            ),
+        )
+    }
+
+    fn get(&self, name: ClipName) -> &Clip {
+        self.clips
+            .get(&name)
+            .unwrap_or_else(|| &self.clips[&ClipName::Idle])
+    }
+}
+
+/// Horizontal speed at which a character starts leaning into the movement.
+const MOVE_SPEED: f64 = 30.0;
+
+/// Which clip a character plays and how far into it it is. Movement picks
+/// between `Idle`, `MoveLeft` and `MoveRight`, `Hurt` and `Death` play over
+/// that once.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Animator {
+    set: Rc<AnimationSet>,
+    movement: ClipName,
+    movement_time: f64,
+    effect: Option<(ClipName, f64)>,
+}
+
+impl Animator {
+    pub fn new(set: AnimationSet) -> Self {
+        Self {
+            set: Rc::new(set),
+            movement: ClipName::Idle,
+            movement_time: 0.0,
+            effect: None,
+        }
+    }
+
+    pub fn tick(&mut self, delta: f64, horizontal_speed: f64) {
+        let movement = if horizontal_speed < -MOVE_SPEED {
+            ClipName::MoveLeft
+        } else if horizontal_speed > MOVE_SPEED {
+            ClipName::MoveRight
+        } else {
+            ClipName::Idle
+        };
+        if movement != self.movement {
+            self.movement = movement;
+            self.movement_time = 0.0;
+        }
+        self.movement_time += delta;
+
+        if let Some((name, time)) = self.effect.as_mut() {
+            *time += delta;
+            // Death stays on its last frame until the owner removes it.
+            if *name != ClipName::Death && self.set.get(*name).is_finished(*time) {
+                self.effect = None;
+            }
+        }
+    }
+
+    /// Plays `Hurt` or `Death` from the start. Nothing interrupts a death.
+    pub fn trigger(&mut self, name: ClipName) {
+        if name != ClipName::Death && !self.set.clips.contains_key(&name) {
+            return;
+        }
+        if !matches!(self.effect, Some((ClipName::Death, _))) {
+            self.effect = Some((name, 0.0));
+        }
+    }
+
+    /// Whether the death clip has played out, or there is none to play.
+    pub fn is_dead(&self) -> bool {
+        match self.effect {
+            Some((ClipName::Death, time)) => match self.set.clips.get(&ClipName::Death) {
+                Some(clip) => clip.is_finished(time),
+                None => true,
+            },
+            _ => false,
+        }
+    }
+
+    pub fn frame(&self) -> &AnimationFrame {
+        match self.effect {
+            Some((name, time)) => self.set.get(name).frame_at(time),
+            None => self.set.get(self.movement).frame_at(self.movement_time),
+        }
+    }
+}
@@ -1,36 +1,95 @@
-use std::collections::HashSet;
-
-use gloo::{events::EventListener, utils::document};
+use gloo::{
+    events::EventListener,
+    utils::{document, window},
+};
 use gloo_render::{request_animation_frame, AnimationFrame};
-use wasm_bindgen::{JsCast, JsValue};
+use js_sys::Date;
+use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::spawn_local;
+use wasm_bindgen_futures::JsFuture;
 use web_sys::{
-    CanvasRenderingContext2d, HtmlAudioElement, HtmlCanvasElement, HtmlImageElement, KeyboardEvent,
+    CanvasRenderingContext2d, Event, GamepadEvent, HtmlAudioElement, HtmlCanvasElement,
+    HtmlImageElement, HtmlInputElement, KeyboardEvent, TouchEvent,
 };
-use yew::{html, Component, Context, NodeRef};
+use yew::{html, html::Scope, Component, Context, NodeRef, TargetCast};
 
 use crate::{
+    assets::{AssetList, Assets, SoundId, TextureId},
+    atlas::{Atlas, ATLAS_PATH},
     audio::AudioManager,
-    download::{download_audio, download_image, Download},
+    controls::{ControlsResult, ControlsScreen},
+    debug::DebugTools,
+    difficulty::Difficulty,
+    download::{download_atlas, download_audio, download_image, Download, LoadProgress},
+    events::WorldEvent,
+    gamepad::GamepadConfig,
     geometry::Vector,
+    hud::{Hud, HudInfo},
+    input::{Action, Bindings, Input},
     level::{l1, l2, Level},
+    music::MusicController,
+    practice::{PracticeOptions, PracticeResult, PracticeScreen, PracticeStats, PracticeTarget},
+    records::{Records, Run, ScoreEntry},
+    records_screen::{NameEntry, NameEntryResult, RecordsScreen},
+    render::{
+        Canvas2dRenderer, Color, Frame, Renderer, RendererKind, WebGlRenderer, CANVAS_HEIGHT,
+        CANVAS_WIDTH,
+    },
+    save::{Checkpoint, SaveFile},
+    screens::{self, draw_text_screen, GameOverKind, Menu, MenuInput, MenuItem, MenuKind, Screen},
+    settings::Settings,
+    sound_bank::SoundBank,
     textures::TextureManager,
-    world::{BulletType, World},
+    touch::Gesture,
+    world::{BulletType, World, WorldSnapshot, START_LIVES},
 };
 
+const BACKGROUND: Color = Color::rgb(100, 100, 255);
+
+/// Longest step the world is ticked by. Frames can still stall without the
+/// page losing focus, and one long step would teleport the bullets.
+const MAX_FRAME_TIME: f64 = 0.1;
+
+/// Failed downloads named on the error screen, the rest are only counted.
+const SHOWN_FAILURES: usize = 4;
+
+/// How fast the player moves, and the share of it kept while focusing.
+const PLAYER_SPEED: f64 = 300.0;
+const FOCUS_SPEED: f64 = 0.4;
+
 pub enum Msg {
     KeyDown(KeyboardEvent),
     KeyUp(KeyboardEvent),
     Timer(f64),
     DownloadRequested(Download),
-    ImageDownloaded(String, HtmlImageElement),
-    AudioDownloaded(String, HtmlAudioElement),
+    TextureDownloaded(TextureId, Result<HtmlImageElement, String>),
+    SoundDownloaded(SoundId, Result<HtmlAudioElement, String>),
+    AtlasDownloaded(Option<(Atlas, HtmlImageElement)>),
+    GamepadConnected(u32),
+    GamepadDisconnected(u32),
+    TouchStart(TouchEvent),
+    TouchMove(TouchEvent),
+    TouchEnd(TouchEvent),
+    TouchCancel(TouchEvent),
+    TouchButton(Action),
+    FocusLost,
+    ImportFileChosen(Event),
+    SaveImported(Result<String, String>),
 }
 
 pub struct App {
+    link: Scope<Self>,
+
     canvas_ref: NodeRef,
 
-    context: Option<CanvasRenderingContext2d>,
+    overlay_ref: NodeRef,
+
+    /// The hidden file picker behind "Import save".
+    import_ref: NodeRef,
+
+    renderer: Option<Box<dyn Renderer>>,
+    renderer_kind: RendererKind,
+    frame: Frame,
 
     world: World,
 
@@ -38,31 +97,50 @@ pub struct App {
     gun_cooldown: f64,
     bullet_type: BulletType,
 
-    game_state: GameState,
+    screens: Vec<Screen>,
+    settings: Settings,
 
-    down_list: HashSet<String>,
+    input: Input,
+    controls: ControlsScreen,
+    hud: Hud,
+    /// Only in debug builds, players could cheat with them otherwise.
+    debug: Option<DebugTools>,
 
+    assets: Assets,
     texture_manager: TextureManager,
     audio_manager: AudioManager,
-    unfinished_downloads: usize,
+    music: MusicController,
+    sound_bank: SoundBank,
+    loading: LoadProgress,
 
     current_level: usize,
     levels: Vec<Level>,
 
+    records: Records,
+    run: Run,
+    /// Where the current level started, also kept in the save file.
+    checkpoint: Option<Checkpoint>,
+    /// How the last export or import went, shown on the records menu.
+    records_status: Option<String>,
+
+    /// What is being practiced, instead of playing a run.
+    practice: Option<PracticeTarget>,
+    /// Selected when the practice list opens again.
+    practice_selected: Option<PracticeTarget>,
+    practice_options: PracticeOptions,
+    practice_stats: PracticeStats,
+    /// Kept with `Action::SaveSnapshot` in the level or practice being
+    /// played. As JSON, to go through the same serialization a snapshot
+    /// stored anywhere else would.
+    snapshot: Option<String>,
+
     _keydown_listener: EventListener,
     _keyup_listener: EventListener,
+    _gamepad_listeners: [EventListener; 2],
+    _focus_listeners: [EventListener; 2],
     _frame: Option<AnimationFrame>,
 }
 
-#[derive(PartialEq)]
-enum GameState {
-    Loading,
-    FinishLoading,
-    Playing,
-    LevelFinished,
-    Lost,
-}
-
 impl Component for App {
     type Message = Msg;
 
@@ -81,28 +159,102 @@ impl Component for App {
             onkeyup.emit(e);
         });
 
-        let levels = vec![l1(), l2()];
+        let onconnected = ctx.link().callback(Msg::GamepadConnected);
+        let ondisconnected = ctx.link().callback(Msg::GamepadDisconnected);
+        let gamepad_listeners = [
+            EventListener::new(&window(), "gamepadconnected", move |e| {
+                let e = e.clone().unchecked_into::<GamepadEvent>();
+                if let Some(gamepad) = e.gamepad() {
+                    onconnected.emit(gamepad.index());
+                }
+            }),
+            EventListener::new(&window(), "gamepaddisconnected", move |e| {
+                let e = e.clone().unchecked_into::<GamepadEvent>();
+                if let Some(gamepad) = e.gamepad() {
+                    ondisconnected.emit(gamepad.index());
+                }
+            }),
+        ];
+
+        let onblur = ctx.link().callback(|_| Msg::FocusLost);
+        let onhidden = ctx.link().callback(|_| Msg::FocusLost);
+        let focus_listeners = [
+            EventListener::new(&window(), "blur", move |_| {
+                onblur.emit(());
+            }),
+            EventListener::new(&document(), "visibilitychange", move |_| {
+                if document().hidden() {
+                    onhidden.emit(());
+                }
+            }),
+        ];
+
+        let assets = Assets::load();
+        let levels = vec![l1(&assets), l2(&assets)];
         let current_level = 0;
+        // An old or edited save may name a level that isn't there.
+        let checkpoint = SaveFile::load().checkpoint.filter(|it| {
+            let exists = it.level < levels.len();
+            if !exists {
+                log::warn!("Not continuing on level {}, it isn't there", it.level + 1);
+            }
+            exists
+        });
+
+        let settings = Settings::load();
+        let mut music = MusicController::new();
+        music.set_volume(settings.music_volume);
+        let mut sound_bank = SoundBank::new(600.0, &assets);
+        sound_bank.set_volume(settings.sound_volume);
 
         Self {
+            link: ctx.link().clone(),
             canvas_ref: NodeRef::default(),
-            context: None,
-            world: World::new(Vector::new(600.0, 1000.0), levels[current_level].clone()),
-            down_list: HashSet::new(),
+            overlay_ref: NodeRef::default(),
+            import_ref: NodeRef::default(),
+            renderer: None,
+            renderer_kind: settings.renderer,
+            frame: Frame::default(),
+            world: World::new(
+                Vector::new(600.0, 1000.0),
+                levels[current_level].clone(),
+                &assets,
+            ),
+            input: Input::new(Bindings::load(), GamepadConfig::load()),
+            controls: ControlsScreen::new(),
+            hud: Hud::new(),
+            debug: cfg!(debug_assertions).then(|| DebugTools::new(&levels)),
             last_tick: -1.0,
-            game_state: GameState::Loading,
+            screens: vec![Screen::Loading],
+            settings,
             bullet_type: BulletType::PlayerSniper,
             gun_cooldown: 0.0,
 
+            assets,
             texture_manager: TextureManager::new(),
             audio_manager: AudioManager::new(),
-            unfinished_downloads: 0,
+            music,
+            sound_bank,
+            loading: LoadProgress::default(),
 
             current_level,
             levels,
 
+            records: Records::load(),
+            run: Run::new(Difficulty::default()),
+            checkpoint,
+            records_status: None,
+
+            practice: None,
+            practice_selected: None,
+            practice_options: PracticeOptions::default(),
+            practice_stats: PracticeStats::load(),
+            snapshot: None,
+
             _keydown_listener: keydown_listener,
             _keyup_listener: keyup_listener,
+            _gamepad_listeners: gamepad_listeners,
+            _focus_listeners: focus_listeners,
             _frame: None,
         }
     }
@@ -111,161 +263,187 @@ impl Component for App {
         match msg {
             Msg::KeyDown(e) => {
                 let key = e.code();
-                if self.game_state == GameState::FinishLoading && key.as_str() == "Enter" {
-                    self.request_frame(ctx);
+                if let Some(Screen::Controls) = self.screens.last() {
+                    if self.controls.key_down(&key, &mut self.input) == ControlsResult::Close {
+                        self.input.bindings.save();
+                        self.input.gamepad.config.save();
+                        self.screens.pop();
+                    }
                     return false;
                 }
-                if (self.game_state == GameState::Lost
-                    || self.game_state == GameState::LevelFinished)
-                    && key.as_str() == "Enter"
-                {
-                    self.world.reset(self.levels[self.current_level].clone());
-                    self.start(ctx);
+                if let Some(Screen::NameEntry(entry)) = self.screens.last_mut() {
+                    let result = entry.key_down(&e.key());
+                    self.name_entered(result);
                     return false;
                 }
-                if key.as_str() == "ControlLeft" {
-                    if self.bullet_type == BulletType::PlayerSniper {
-                        self.bullet_type = BulletType::PlayerHeavy;
-                    } else {
-                        self.bullet_type = BulletType::PlayerSniper;
+                if let (Some(Screen::Playing), Some(debug)) =
+                    (self.screens.last(), self.debug.as_mut())
+                {
+                    if debug.key_down(&key, &mut self.world) {
+                        self.check_tampering();
+                        return false;
                     }
-                    return false;
                 }
-                if !self.down_list.contains(&key) {
-                    self.down_list.insert(key);
+
+                for action in self.input.key_down(key) {
+                    self.handle_action(action);
                 }
                 false
             }
             Msg::KeyUp(e) => {
-                let key = e.code();
-                if self.down_list.contains(&key) {
-                    self.down_list.remove(&key);
+                self.input.key_up(&e.code());
+                false
+            }
+            Msg::FocusLost => {
+                self.input.clear();
+                if let Some(Screen::Playing) = self.screens.last() {
+                    self.pause();
                 }
                 false
             }
-            Msg::Timer(time) => {
-                match self.game_state {
-                    GameState::Playing => self.request_frame(ctx),
-                    GameState::FinishLoading => self.start(ctx),
-                    GameState::Lost => {
-                        AudioManager::stop(&self.audio_manager.get(&self.world.level.sound));
-                        return false;
-                    }
-                    GameState::LevelFinished => {
-                        AudioManager::stop(&self.audio_manager.get(&self.world.level.sound));
-                        return false;
-                    }
-                    GameState::Loading => return false,
+            Msg::GamepadConnected(index) => {
+                self.input.gamepad.connect(index);
+                false
+            }
+            Msg::GamepadDisconnected(index) => {
+                self.input.gamepad.disconnect(index);
+                false
+            }
+            Msg::TouchStart(e) => {
+                e.prevent_default();
+                if let Some(Screen::Playing) = self.screens.last() {
+                    self.input.touch.start(&e);
+                } else {
+                    self.input.touch.gesture_start(&e);
                 }
-
-                if self.last_tick < 0.0 {
-                    self.last_tick = time;
-                    return false;
+                false
+            }
+            Msg::TouchMove(e) => {
+                e.prevent_default();
+                let scale = self.canvas_scale();
+                self.input.touch.moved(&e, scale);
+                false
+            }
+            Msg::TouchEnd(e) => {
+                e.prevent_default();
+                self.input.touch.end(&e);
+                if let Some(gesture) = self.input.touch.gesture_end(&e) {
+                    self.gesture(gesture);
                 }
-                let delta_time = (time - self.last_tick) / 1000.0;
+                false
+            }
+            Msg::TouchCancel(e) => {
+                self.input.touch.cancel(&e);
+                false
+            }
+            Msg::TouchButton(action) => {
+                self.handle_action(action);
+                false
+            }
+            Msg::Timer(time) => {
+                self.hud.frame(time);
+                self.request_frame(ctx);
+
+                let delta_time = if self.last_tick < 0.0 {
+                    0.0
+                } else {
+                    ((time - self.last_tick) / 1000.0).min(MAX_FRAME_TIME)
+                };
                 self.last_tick = time;
 
-                if self.gun_cooldown > 0.0 {
-                    self.gun_cooldown -= delta_time;
-                }
-
-                let mut delta = Vector::zero();
-
-                for key in self.down_list.iter() {
-                    match key.as_ref() {
-                        "ArrowLeft" => delta.x += -300.0,
-                        "ArrowRight" => delta.x += 300.0,
-                        "ArrowUp" => delta.y += -300.0,
-                        "ArrowDown" => delta.y += 300.0,
-                        "Space" => {
-                            if self.gun_cooldown <= 0.0 {
-                                self.world.shoot(
-                                    Vector::new(0.0, -500.0),
-                                    self.bullet_type,
-                                    &self.audio_manager,
-                                );
-                                self.gun_cooldown += 0.2;
-                            }
-                        }
-                        _ => (),
-                    }
+                for action in self.input.poll_gamepad() {
+                    self.handle_action(action);
                 }
-                self.world.move_player(delta * delta_time);
-                match self.world.tick(delta_time, &self.audio_manager) {
-                    crate::world::TickResult::None => {
-                        self.world
-                            .draw(self.context.as_ref().unwrap(), &self.texture_manager);
-                    }
-                    crate::world::TickResult::Win => {
-                        self.current_level += 1;
-                        self.world.player_bullets += 1;
-                        if self.current_level != self.levels.len() {
-                            self.game_state = GameState::LevelFinished;
-                            self.game_over(GameOverKind::LevelFinished);
-                        } else {
-                            self.game_state = GameState::Lost;
-                            self.game_over(GameOverKind::Won);
+                self.music.tick(delta_time);
+
+                if let Some(Screen::Menu(menu)) = self.screens.last_mut() {
+                    menu.tick(delta_time);
+                }
+                match self.screens.last() {
+                    Some(Screen::Playing) => {
+                        self.check_tampering();
+                        let delta_time = match self.debug.as_mut() {
+                            Some(debug) => debug.world_delta(delta_time),
+                            None => Some(delta_time),
+                        };
+                        if let Some(delta_time) = delta_time {
+                            self.tick_world_in_steps(delta_time * self.world.time_scale());
                         }
                     }
-                    crate::world::TickResult::Loose => {
-                        self.game_state = GameState::Lost;
-                        self.game_over(GameOverKind::Lost);
+                    Some(Screen::Menu(menu)) if matches!(menu.kind, MenuKind::Results(_)) => {
+                        self.world.tick_effects(delta_time);
                     }
+                    _ => (),
                 }
+                self.draw();
                 false
             }
-            Msg::ImageDownloaded(path, img) => {
-                log::debug!("Msg::ImageDownloaded");
-                self.texture_manager.insert(path, img);
-                self.unfinished_downloads -= 1;
-
-                if self.unfinished_downloads == 0 {
-                    self.game_state = GameState::FinishLoading;
-                }
-
+            Msg::TextureDownloaded(id, result) => {
+                log::debug!("Msg::TextureDownloaded");
+                let failure = match result {
+                    Ok(img) => {
+                        self.texture_manager.insert(&self.assets, id, img);
+                        None
+                    }
+                    Err(e) => Some(format!("{} ({})", self.assets.texture_entry(id).path, e)),
+                };
+                self.download_finished(Download::Texture(id), failure);
                 false
             }
-            Msg::AudioDownloaded(path, audio) => {
-                self.audio_manager.insert(path, audio);
-                self.unfinished_downloads -= 1;
-
-                if self.unfinished_downloads == 0 {
-                    self.game_state = GameState::FinishLoading;
+            Msg::SoundDownloaded(id, result) => {
+                let failure = match result {
+                    Ok(audio) => {
+                        self.audio_manager.insert(id, audio);
+                        None
+                    }
+                    Err(e) => Some(format!("{} ({})", self.assets.sound_entry(id).path, e)),
+                };
+                self.download_finished(Download::Sound(id), failure);
+                false
+            }
+            Msg::AtlasDownloaded(atlas) => {
+                if let Some((atlas, img)) = atlas {
+                    self.texture_manager.insert_atlas(&self.assets, atlas, img);
                 }
-
+                // Whatever the atlas didn't have is loaded image by image.
+                self.stream_level(0);
+                self.download_finished(Download::Atlas(ATLAS_PATH.to_string()), None);
                 false
             }
             Msg::DownloadRequested(download) => {
-                self.game_state = GameState::Loading;
-                self.unfinished_downloads += 1;
-
-                self.game_over(GameOverKind::Loading);
-
-                match download {
-                    Download::Image(path) => {
-                        let callback = ctx
-                            .link()
-                            .clone()
-                            .callback(|(str, img)| Msg::ImageDownloaded(str, img));
-
-                        spawn_local(async move {
-                            let img = download_image(&path).await;
-                            callback.emit((path, img));
-                        });
+                self.download(download, true);
+                false
+            }
+            Msg::ImportFileChosen(e) => {
+                let input = e.target_unchecked_into::<HtmlInputElement>();
+                let file = match input.files().and_then(|it| it.get(0)) {
+                    Some(file) => file,
+                    None => return false,
+                };
+                let callback = self.link.callback(Msg::SaveImported);
+                spawn_local(async move {
+                    let text = JsFuture::from(file.text())
+                        .await
+                        .ok()
+                        .and_then(|it| it.as_string())
+                        .ok_or_else(|| "the file could not be read".to_string());
+                    callback.emit(text);
+                });
+                false
+            }
+            Msg::SaveImported(json) => {
+                match json.and_then(|it| Records::import(&it)) {
+                    Ok(records) => {
+                        self.records = records;
+                        self.records.save();
+                        self.records_status =
+                            Some(format!("Imported {} runs", self.records.history().len()));
                     }
-                    Download::Audio(path) => {
-                        let callback = ctx
-                            .link()
-                            .clone()
-                            .callback(|(str, audio)| Msg::AudioDownloaded(str, audio));
-
-                        spawn_local(async move {
-                            let img = download_audio(&path).await;
-                            callback.emit((path, img));
-                        });
+                    Err(e) => {
+                        log::error!("Failed to import save: {}", e);
+                        self.records_status = Some(format!("Import failed: {}", e));
                     }
                 }
-
                 false
             }
         }
@@ -274,134 +452,903 @@ impl Component for App {
     fn view(&self, ctx: &Context<Self>) -> yew::Html {
         html! {
             <div class="main-block">
-                <canvas
-                    class="main-canvas"
-                    ref={self.canvas_ref.clone()}
-                    width={600}
-                    height={1000}
-                    onkeydown={ctx.link().callback(Msg::KeyDown)}
+                <div class="canvas-stack">
+                    <canvas
+                        class="main-canvas"
+                        ref={self.canvas_ref.clone()}
+                        width={CANVAS_WIDTH.to_string()}
+                        height={CANVAS_HEIGHT.to_string()}
+                        onkeydown={ctx.link().callback(Msg::KeyDown)}
+                        ontouchstart={ctx.link().callback(Msg::TouchStart)}
+                        ontouchmove={ctx.link().callback(Msg::TouchMove)}
+                        ontouchend={ctx.link().callback(Msg::TouchEnd)}
+                        ontouchcancel={ctx.link().callback(Msg::TouchCancel)}
+                    />
+                    <canvas
+                        class="overlay-canvas"
+                        ref={self.overlay_ref.clone()}
+                        width={CANVAS_WIDTH.to_string()}
+                        height={CANVAS_HEIGHT.to_string()}
+                    />
+                </div>
+                <input
+                    type="file"
+                    accept=".json,application/json"
+                    style="display: none"
+                    ref={self.import_ref.clone()}
+                    onchange={ctx.link().callback(Msg::ImportFileChosen)}
                 />
+                <div class="touch-buttons">
+                    { self.touch_button(ctx, "Bomb", Action::Bomb) }
+                    { self.touch_button(ctx, "Weapon", Action::SwitchWeapon) }
+                    { self.touch_button(ctx, "Pause", Action::Pause) }
+                </div>
             </div>
         }
     }
 
     fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
         if first_render {
-            let canvas = self.canvas_ref.cast::<HtmlCanvasElement>().unwrap();
-
-            self.context = Some(
-                canvas
-                    .get_context("2d")
-                    .unwrap()
-                    .unwrap()
-                    .dyn_into::<CanvasRenderingContext2d>()
-                    .unwrap(),
-            );
+            self.renderer = Some(self.create_renderer());
 
-            for file in self.required_textures() {
-                ctx.link()
-                    .send_message(Msg::DownloadRequested(Download::Image(file)));
-            }
-            for file in self.required_audio() {
-                ctx.link()
-                    .send_message(Msg::DownloadRequested(Download::Audio(file)));
-            }
+            ctx.link()
+                .send_message(Msg::DownloadRequested(Download::Atlas(
+                    ATLAS_PATH.to_string(),
+                )));
+            self.request_frame(ctx);
         }
     }
 }
 
 impl App {
-    fn start(&mut self, ctx: &Context<Self>) {
+    fn start(&mut self) {
         self.last_tick = -1.0;
-        self.game_state = GameState::Playing;
-        self.request_frame(ctx);
-        AudioManager::play(
-            self.audio_manager.get(&self.world.level.sound),
-            true,
-            false,
-            0.5,
-        );
-    }
-
-    fn game_over(&self, kind: GameOverKind) {
-        let context = self.context.as_ref().unwrap();
-        context.save();
-        context.set_global_composite_operation("copy").unwrap();
-        context.set_fill_style(&JsValue::from_str(kind.color()));
-        context.fill_rect(0.0, 0.0, 601.0, 1000.0);
-        context.restore();
-        context.set_font("48px bold");
-        context.set_text_align("center");
-        context.set_fill_style(&JsValue::from_str("white"));
-        let mut top = 500.0;
-        for text in kind.text(self) {
-            context.fill_text(&text, 300.0, top).unwrap();
-            top += 100.0;
+        // A finger that stayed down through a menu must not move the player.
+        self.input.touch.clear();
+        self.music.resume();
+        while !matches!(self.screens.last(), Some(Screen::Playing) | None) {
+            self.screens.pop();
+        }
+        if self.screens.is_empty() {
+            self.screens.push(Screen::Playing);
         }
     }
 
-    fn request_frame(&mut self, ctx: &Context<Self>) {
-        self._frame = Some({
-            let link = ctx.link().clone();
-            request_animation_frame(move |time| link.send_message(Msg::Timer(time)))
-        })
+    fn pause(&mut self) {
+        self.music.pause();
+        self.screens.push(Screen::Menu(Menu::new(MenuKind::Pause)));
     }
 
-    fn required_textures(&self) -> impl Iterator<Item = String> {
-        [
-            "resources/ghost.png".to_string(),
-            "resources/witch.png".to_string(),
-            "resources/missile.png".to_string(),
-            "resources/missile_2.png".to_string(),
-            "resources/Forest.png".to_string(),
-            "resources/Floor.png".to_string(),
-            "resources/hearth.png".to_string(),
-            "resources/green_hearth.png".to_string(),
-        ]
-        .into_iter()
-    }
-
-    fn required_audio(&self) -> impl Iterator<Item = String> {
-        [
-            "resources/resurrection.mp3".to_string(),
-            "resources/cypis.mp3".to_string(),
-            "resources/shoot.wav".to_string(),
-            "resources/shoot_2.wav".to_string(),
-            "resources/shoot_3.wav".to_string(),
-        ]
-        .into_iter()
+    fn game_over(&mut self, kind: GameOverKind) {
+        self.screens
+            .push(Screen::Menu(Menu::new(MenuKind::Results(kind))));
     }
-}
 
-#[derive(Clone, Copy)]
-enum GameOverKind {
-    Lost,
-    Won,
-    LevelFinished,
-    Loading,
-}
+    /// Keeps runs played with the debug tools off the high score tables.
+    fn check_tampering(&mut self) {
+        if let Some(debug) = self.debug.as_mut() {
+            if debug.tampered(&self.world) {
+                self.run.tainted = true;
+            }
+        }
+    }
+
+    /// Snapshots are for practice. In a run they come with the debug tools,
+    /// and going back to one keeps the run off the high score tables.
+    fn snapshots_allowed(&self) -> bool {
+        self.practice.is_some() || self.debug.is_some()
+    }
+
+    fn save_snapshot(&mut self) {
+        match serde_json::to_string(&self.world.snapshot()) {
+            Ok(json) => {
+                log::info!("Saved a snapshot, {} bytes", json.len());
+                self.snapshot = Some(json);
+            }
+            Err(e) => log::error!("Failed to save a snapshot: {}", e),
+        }
+    }
+
+    fn restore_snapshot(&mut self) {
+        let json = match &self.snapshot {
+            Some(json) => json,
+            None => return,
+        };
+        match serde_json::from_str::<WorldSnapshot>(json) {
+            Ok(snapshot) => {
+                self.world.restore(snapshot);
+                if self.practice.is_none() {
+                    self.run.tainted = true;
+                }
+            }
+            Err(e) => log::error!("Failed to restore the snapshot: {}", e),
+        }
+    }
+
+    /// Time sped up for debugging can add up to more than `MAX_FRAME_TIME`,
+    /// it is split into steps no longer than that.
+    fn tick_world_in_steps(&mut self, mut delta_time: f64) {
+        loop {
+            let step = delta_time.min(MAX_FRAME_TIME);
+            self.tick_world(step);
+            delta_time -= step;
+            if delta_time <= 0.0 || !matches!(self.screens.last(), Some(Screen::Playing)) {
+                break;
+            }
+        }
+    }
+
+    fn tick_world(&mut self, delta_time: f64) {
+        if self.gun_cooldown > 0.0 {
+            self.gun_cooldown -= delta_time;
+        }
+
+        self.world.focused = self.input.is_held(Action::Focus);
+        let speed = if self.world.focused {
+            PLAYER_SPEED * FOCUS_SPEED
+        } else {
+            PLAYER_SPEED
+        };
+        let delta = self.input.movement() * speed;
+        if self.input.is_held(Action::Shoot) && self.gun_cooldown <= 0.0 {
+            self.world.shoot(Vector::new(0.0, -500.0), self.bullet_type);
+            self.gun_cooldown += 0.2;
+        }
+        self.world
+            .move_player(delta * delta_time + self.input.touch.take_drag());
+        let tick_result = self.world.tick(delta_time);
+        let events = self.world.take_events();
+        for event in events.iter() {
+            if let WorldEvent::PlayerHit { .. } = event {
+                if self.practice.is_none() {
+                    self.run.deaths += 1;
+                }
+            }
+            self.music
+                .handle(event, &self.world.level.music, &self.audio_manager);
+        }
+        self.sound_bank
+            .play(&events, &self.world.level.sounds, &self.audio_manager);
+        self.sound_bank.tick(delta_time);
+        match tick_result {
+            crate::world::TickResult::None => (),
+            crate::world::TickResult::Win if self.practice.is_some() => {
+                if let Some(target) = self.practice {
+                    self.practice_stats.cleared(target);
+                }
+                self.game_over(GameOverKind::PracticeCleared);
+            }
+            crate::world::TickResult::Loose if self.practice.is_some() => {
+                self.game_over(GameOverKind::PracticeFailed)
+            }
+            crate::world::TickResult::Win => {
+                let start = self.checkpoint.as_ref().map_or(0, |it| it.score);
+                self.run
+                    .level_finished(self.world.score.saturating_sub(start));
+                self.current_level += 1;
+                self.world.player_bullets += 1;
+                if self.current_level != self.levels.len() {
+                    // A reload goes on with the next level.
+                    self.save_checkpoint();
+                    self.game_over(GameOverKind::LevelFinished);
+                } else {
+                    self.game_over(GameOverKind::Won);
+                }
+            }
+            crate::world::TickResult::Loose => self.game_over(GameOverKind::Lost),
+        }
+    }
+
+    /// `waiting` is false for prefetching, which happens behind the
+    /// player's back.
+    fn download(&mut self, download: Download, waiting: bool) {
+        self.loading.started(download.clone(), waiting);
+
+        match download {
+            Download::Texture(id) => {
+                let path = self.assets.texture_entry(id).path.clone();
+                let callback = self
+                    .link
+                    .callback(move |img| Msg::TextureDownloaded(id, img));
+
+                spawn_local(async move {
+                    callback.emit(download_image(&path).await);
+                });
+            }
+            Download::Sound(id) => {
+                let path = self.assets.sound_entry(id).path.clone();
+                let callback = self
+                    .link
+                    .callback(move |audio| Msg::SoundDownloaded(id, audio));
+
+                spawn_local(async move {
+                    callback.emit(download_audio(&path).await);
+                });
+            }
+            Download::Atlas(path) => {
+                let callback = self.link.callback(Msg::AtlasDownloaded);
+
+                spawn_local(async move {
+                    callback.emit(download_atlas(&path).await);
+                });
+            }
+        }
+    }
+
+    fn create_renderer(&mut self) -> Box<dyn Renderer> {
+        let canvas = self.canvas_ref.cast::<HtmlCanvasElement>().unwrap();
+        let overlay = self
+            .overlay_ref
+            .cast::<HtmlCanvasElement>()
+            .unwrap()
+            .get_context("2d")
+            .unwrap()
+            .unwrap()
+            .dyn_into::<CanvasRenderingContext2d>()
+            .unwrap();
+
+        if self.renderer_kind == RendererKind::WebGl {
+            match WebGlRenderer::new(&canvas, overlay.clone()) {
+                Ok(renderer) => return Box::new(renderer),
+                Err(e) => {
+                    log::warn!("Falling back to Canvas 2D: {}", e);
+                    self.renderer_kind = RendererKind::Canvas2d;
+                }
+            }
+        }
+        // A canvas that already handed out a WebGL context refuses a 2D one,
+        // the overlay is drawn on instead then.
+        let context = canvas
+            .get_context("2d")
+            .ok()
+            .flatten()
+            .and_then(|it| it.dyn_into::<CanvasRenderingContext2d>().ok())
+            .unwrap_or(overlay);
+        Box::new(Canvas2dRenderer::new(context))
+    }
+
+    fn draw(&mut self) {
+        let mut frame = std::mem::take(&mut self.frame);
+        frame.clear();
+
+        let in_game = self.screens.iter().any(|it| matches!(it, Screen::Playing));
+        if in_game {
+            self.world.draw(&mut frame, &self.texture_manager);
+            self.hud.draw(
+                &mut frame,
+                &self.world,
+                &HudInfo {
+                    level: self.current_level.min(self.levels.len() - 1),
+                    levels: self.levels.len(),
+                    weapon: self.bullet_type,
+                    difficulty: self.run.difficulty,
+                    snapshot: self.snapshots_allowed().then(|| self.snapshot.is_some()),
+                },
+            );
+            if let Some(debug) = &self.debug {
+                debug.draw(&mut frame, &self.world);
+            }
+        } else {
+            screens::fill(&mut frame, BACKGROUND);
+        }
+
+        match self.screens.last() {
+            Some(Screen::Loading) | None => {
+                let (finished, total) = self.loading.counts();
+                screens::draw_loading(&mut frame, BACKGROUND, finished, total);
+            }
+            Some(Screen::Title) => draw_text_screen(
+                &mut frame,
+                BACKGROUND,
+                &[
+                    "Tohou".to_string(),
+                    format!("Press {} to start.", self.key_name(Action::Confirm)),
+                ],
+            ),
+            Some(Screen::Credits) => draw_text_screen(
+                &mut frame,
+                BACKGROUND,
+                &[
+                    "Tohou clone".to_string(),
+                    "Code and levels by".to_string(),
+                    "Tetrergeru".to_string(),
+                    "Thanks for playing!".to_string(),
+                ],
+            ),
+            Some(Screen::Controls) => self.controls.draw(&mut frame, &self.input),
+            Some(Screen::NameEntry(entry)) => entry.draw(&mut frame),
+            Some(Screen::Records(screen)) => {
+                screen.draw(&mut frame, &self.records, self.levels.len())
+            }
+            Some(Screen::Practice(screen)) => screen.draw(&mut frame, &self.practice_stats),
+            Some(Screen::Playing) => (),
+            Some(Screen::Menu(menu)) => menu.draw(&mut frame, &self.menu_title(menu.kind), |it| {
+                self.item_label(it)
+            }),
+        }
+
+        if let Some(renderer) = self.renderer.as_mut() {
+            renderer.render(&frame, &self.texture_manager);
+        }
+        self.frame = frame;
+    }
+
+    fn menu_title(&self, kind: MenuKind) -> Vec<String> {
+        match kind {
+            MenuKind::Main { .. } => vec!["Tohou".into()],
+            MenuKind::Pause => vec!["Paused".into()],
+            MenuKind::Options => vec!["Options".into()],
+            MenuKind::Records => {
+                let mut title = vec!["Records".to_string()];
+                title.extend(self.records_status.clone());
+                title
+            }
+            MenuKind::Results(GameOverKind::Lost) => vec!["You have lost.".into()],
+            MenuKind::Results(GameOverKind::Won) => vec!["You have won!".into()],
+            MenuKind::Results(GameOverKind::PracticeCleared) => vec!["Practice cleared!".into()],
+            MenuKind::Results(GameOverKind::PracticeFailed) => vec!["Practice failed.".into()],
+            MenuKind::Results(GameOverKind::LevelFinished) => {
+                vec![format!("Level {} finished!", self.current_level)]
+            }
+            MenuKind::LoadFailed => {
+                let failed: Vec<_> = self.loading.failed_paths().collect();
+                let mut title = vec!["Could not load".to_string()];
+                title.extend(failed.iter().take(SHOWN_FAILURES).map(|it| it.to_string()));
+                if failed.len() > SHOWN_FAILURES {
+                    title.push(format!("and {} more", failed.len() - SHOWN_FAILURES));
+                }
+                title
+            }
+        }
+    }
+
+    fn item_label(&self, item: MenuItem) -> String {
+        match item {
+            MenuItem::Continue => match &self.checkpoint {
+                Some(checkpoint) => format!("Continue (level {})", checkpoint.level + 1),
+                None => "Continue".into(),
+            },
+            MenuItem::Start => "Start".into(),
+            MenuItem::Difficulty => format!("Difficulty: {}", self.settings.difficulty.name()),
+            MenuItem::Resume => "Resume".into(),
+            MenuItem::Restart => "Restart level".into(),
+            MenuItem::Retry => "Try again".into(),
+            MenuItem::NextLevel => "Next level".into(),
+            MenuItem::Options => "Options".into(),
+            MenuItem::Records => "Records".into(),
+            MenuItem::Practice => "Practice".into(),
+            MenuItem::HighScores => "High scores".into(),
+            MenuItem::ExportSave => "Export save".into(),
+            MenuItem::ImportSave => "Import save".into(),
+            MenuItem::Controls => "Controls".into(),
+            MenuItem::Credits => "Credits".into(),
+            MenuItem::QuitToTitle => "Quit to title".into(),
+            MenuItem::RetryLoading => "Retry".into(),
+            MenuItem::MusicVolume => {
+                format!("Music: {:.0}%", self.settings.music_volume * 100.0)
+            }
+            MenuItem::SoundVolume => {
+                format!("Sounds: {:.0}%", self.settings.sound_volume * 100.0)
+            }
+            MenuItem::StickMode => {
+                if self.input.gamepad.config.digital {
+                    "Stick: 8-way".into()
+                } else {
+                    "Stick: analog".into()
+                }
+            }
+            MenuItem::Renderer => {
+                if self.settings.renderer == self.renderer_kind {
+                    format!("Renderer: {}", self.settings.renderer.name())
+                } else {
+                    format!("Renderer: {} (reload)", self.settings.renderer.name())
+                }
+            }
+            MenuItem::Back => "Back".into(),
+        }
+    }
+
+    fn handle_action(&mut self, action: Action) {
+        match self.screens.last_mut() {
+            Some(Screen::Playing) => match action {
+                Action::Pause => self.pause(),
+                Action::SwitchWeapon => {
+                    if self.bullet_type == BulletType::PlayerSniper {
+                        self.bullet_type = BulletType::PlayerHeavy;
+                    } else {
+                        self.bullet_type = BulletType::PlayerSniper;
+                    }
+                }
+                Action::Bomb => self.world.bomb(),
+                Action::SaveSnapshot if self.snapshots_allowed() => self.save_snapshot(),
+                Action::RestoreSnapshot if self.snapshots_allowed() => self.restore_snapshot(),
+                _ => (),
+            },
+            Some(Screen::Title) => {
+                if action == Action::Confirm {
+                    let can_continue = self.checkpoint.is_some();
+                    self.screens
+                        .push(Screen::Menu(Menu::new(MenuKind::Main { can_continue })));
+                }
+            }
+            Some(Screen::Credits) => {
+                if action == Action::Confirm || action == Action::Pause {
+                    self.screens.pop();
+                }
+            }
+            Some(Screen::Menu(menu)) => {
+                let kind = menu.kind;
+                let input = menu.navigate(action);
+                self.menu_input(kind, input);
+            }
+            Some(Screen::NameEntry(entry)) => {
+                let result = entry.action(action);
+                self.name_entered(result);
+            }
+            Some(Screen::Records(screen)) => {
+                if screen.action(action, self.levels.len()) {
+                    self.screens.pop();
+                }
+            }
+            Some(Screen::Practice(screen)) => {
+                let result = screen.action(action);
+                self.practice_options = screen.options;
+                match result {
+                    PracticeResult::Stay => (),
+                    PracticeResult::Close => {
+                        self.screens.pop();
+                    }
+                    PracticeResult::Start(target) => self.start_practice(target),
+                }
+            }
+            Some(Screen::Loading | Screen::Controls) | None => (),
+        }
+    }
+
+    fn menu_input(&mut self, kind: MenuKind, input: Option<MenuInput>) {
+        match input {
+            Some(MenuInput::Select(item)) => self.activate(item, 1.0),
+            Some(MenuInput::Adjust(item, direction)) => self.activate(item, direction),
+            Some(MenuInput::Back) => self.back(kind),
+            None => (),
+        }
+    }
+
+    fn activate(&mut self, item: MenuItem, direction: f64) {
+        match item {
+            MenuItem::Continue => {
+                if let Some(checkpoint) = self.checkpoint.clone() {
+                    self.current_level = checkpoint.level;
+                    self.run = checkpoint.run.clone();
+                    self.world = World::new(Vector::new(600.0, 1000.0), self.level(), &self.assets);
+                    self.world.time = checkpoint.time;
+                    self.restore(&checkpoint);
+                    self.enter_level();
+                }
+            }
+            MenuItem::Start => {
+                self.current_level = 0;
+                self.run = Run::new(self.settings.difficulty);
+                self.world = World::new(Vector::new(600.0, 1000.0), self.level(), &self.assets);
+                self.enter_level();
+            }
+            MenuItem::Resume => self.start(),
+            MenuItem::NextLevel => {
+                self.world.reset(self.level());
+                self.enter_level();
+            }
+            MenuItem::Restart | MenuItem::Retry if self.practice.is_some() => {
+                if let Some(target) = self.practice {
+                    self.start_practice(target);
+                }
+            }
+            MenuItem::Restart | MenuItem::Retry => {
+                self.world.reset(self.level());
+                // A level tried again doesn't keep what was scored in it.
+                if let Some(checkpoint) = self.checkpoint.clone() {
+                    self.restore(&checkpoint);
+                }
+                if item == MenuItem::Retry {
+                    self.world.lives = START_LIVES;
+                }
+                self.start();
+                self.stream_level(self.current_level);
+            }
+            MenuItem::Options => self
+                .screens
+                .push(Screen::Menu(Menu::new(MenuKind::Options))),
+            MenuItem::Records => self
+                .screens
+                .push(Screen::Menu(Menu::new(MenuKind::Records))),
+            MenuItem::HighScores => self.screens.push(Screen::Records(RecordsScreen::new())),
+            MenuItem::ExportSave => {
+                self.records_status = match self.records.export() {
+                    Ok(()) => Some("Exported".to_string()),
+                    Err(e) => Some(format!("Export failed: {}", e)),
+                };
+            }
+            // Browsers only open the file picker in response to the user.
+            MenuItem::ImportSave => {
+                if let Some(input) = self.import_ref.cast::<HtmlInputElement>() {
+                    input.set_value("");
+                    input.click();
+                }
+            }
+            MenuItem::Controls => self.screens.push(Screen::Controls),
+            MenuItem::Credits => self.screens.push(Screen::Credits),
+            MenuItem::Practice => {
+                // From the results of a practice, the list comes back over
+                // the main menu as if it was opened from there.
+                if self.practice.take().is_some() {
+                    self.music.resume();
+                    self.music.fade_out(1.0);
+                    let can_continue = self.checkpoint.is_some();
+                    self.screens = vec![
+                        Screen::Title,
+                        Screen::Menu(Menu::new(MenuKind::Main { can_continue })),
+                    ];
+                }
+                let difficulty = self.settings.difficulty;
+                let levels = self.practice_levels(difficulty);
+                let selected = self.practice_selected;
+                self.screens.push(Screen::Practice(PracticeScreen::new(
+                    &levels,
+                    difficulty,
+                    self.practice_options,
+                    selected,
+                )));
+            }
+            MenuItem::QuitToTitle => {
+                self.music.resume();
+                self.music.fade_out(1.0);
+                // Runs left before they were won or lost can be continued
+                // from the checkpoint.
+                let run_over = matches!(
+                    self.screens.last(),
+                    Some(Screen::Menu(Menu {
+                        kind: MenuKind::Results(GameOverKind::Lost | GameOverKind::Won),
+                        ..
+                    }))
+                );
+                if self.practice.take().is_some() || !run_over {
+                    self.screens = vec![Screen::Title];
+                } else {
+                    self.end_run();
+                }
+                self.current_level = 0;
+            }
+            MenuItem::RetryLoading => {
+                self.screens.pop();
+                self.screens.push(Screen::Loading);
+                for download in self.loading.retry() {
+                    self.link.send_message(Msg::DownloadRequested(download));
+                }
+            }
+            MenuItem::Difficulty => {
+                self.settings.difficulty = self.settings.difficulty.step(direction);
+                self.settings.save();
+            }
+            MenuItem::MusicVolume => {
+                self.settings.music_volume =
+                    Self::step_volume(self.settings.music_volume, direction);
+                self.music.set_volume(self.settings.music_volume);
+            }
+            MenuItem::SoundVolume => {
+                self.settings.sound_volume =
+                    Self::step_volume(self.settings.sound_volume, direction);
+                self.sound_bank.set_volume(self.settings.sound_volume);
+            }
+            MenuItem::StickMode => {
+                let config = &mut self.input.gamepad.config;
+                config.digital = !config.digital;
+            }
+            // A canvas keeps the context it was first asked for, so the
+            // switch only happens on the next page load.
+            MenuItem::Renderer => {
+                self.settings.renderer = match self.settings.renderer {
+                    RendererKind::Canvas2d => RendererKind::WebGl,
+                    RendererKind::WebGl => RendererKind::Canvas2d,
+                };
+            }
+            MenuItem::Back => {
+                if let Some(Screen::Menu(menu)) = self.screens.last() {
+                    self.back(menu.kind);
+                }
+            }
+        }
+    }
 
-impl GameOverKind {
-    fn color(self) -> &'static str {
-        match self {
-            GameOverKind::Lost => "rgba(255, 100, 100, 255)",
-            GameOverKind::Won | GameOverKind::LevelFinished => "rgba(100, 255, 100, 255)",
-            GameOverKind::Loading => "rgba(100, 100, 255, 255)",
+    fn back(&mut self, kind: MenuKind) {
+        match kind {
+            MenuKind::Pause => self.start(),
+            MenuKind::Options => {
+                self.settings.save();
+                self.input.gamepad.config.save();
+                self.screens.pop();
+            }
+            MenuKind::Main { .. } => {
+                self.screens.pop();
+            }
+            MenuKind::Records => {
+                self.records_status = None;
+                self.screens.pop();
+            }
+            MenuKind::Results(_) | MenuKind::LoadFailed => (),
+        }
+    }
+
+    /// The current level as played on the run's difficulty.
+    fn level(&self) -> Level {
+        self.levels[self.current_level]
+            .clone()
+            .for_difficulty(self.run.difficulty)
+    }
+
+    /// Every level as played on `difficulty`.
+    fn practice_levels(&self, difficulty: Difficulty) -> Vec<Level> {
+        self.levels
+            .iter()
+            .map(|it| it.clone().for_difficulty(difficulty))
+            .collect()
+    }
+
+    /// Starts the world right at `target`, outside of any run: nothing is
+    /// saved to continue and no scores are kept, only the practice stats.
+    fn start_practice(&mut self, target: PracticeTarget) {
+        // Trying again keeps the snapshot, to go straight back to it.
+        if self.practice != Some(target) {
+            self.snapshot = None;
+        }
+        self.practice = Some(target);
+        self.practice_selected = Some(target);
+        self.current_level = target.level;
+        // Only for the HUD, the run being continued is in the checkpoint.
+        self.run = Run::new(target.difficulty);
+        let level = target.level(&self.practice_levels(target.difficulty));
+        self.world = World::new(Vector::new(600.0, 1000.0), level, &self.assets);
+        self.world.player_bullets = self.practice_options.power;
+        self.world.lives = self.practice_options.lives;
+        self.world.infinite_lives = self.practice_options.infinite_lives;
+        self.practice_stats.attempted(target);
+
+        self.screens = vec![Screen::Playing];
+        self.start();
+        self.stream_level(self.current_level);
+    }
+
+    /// Starts playing the current level, which the world was set up for,
+    /// and saves where the run stands.
+    fn enter_level(&mut self) {
+        self.save_checkpoint();
+        self.snapshot = None;
+
+        self.screens = vec![Screen::Playing];
+        self.start();
+        self.stream_level(self.current_level);
+    }
+
+    /// The run continues from the start of the current level.
+    fn save_checkpoint(&mut self) {
+        let checkpoint = Checkpoint {
+            level: self.current_level,
+            power: self.world.player_bullets,
+            lives: self.world.lives,
+            score: self.world.score,
+            rank: self.world.rank,
+            time: self.world.time,
+            run: self.run.clone(),
+        };
+        SaveFile::store_checkpoint(Some(checkpoint.clone()));
+        self.checkpoint = Some(checkpoint);
+    }
+
+    fn restore(&mut self, checkpoint: &Checkpoint) {
+        self.world.player_bullets = checkpoint.power;
+        self.world.lives = checkpoint.lives;
+        self.world.score = checkpoint.score;
+        self.world.rank = checkpoint.rank;
+    }
+
+    /// Puts the run that is being quit into the history, and asks for a
+    /// name if it made it onto a high score table.
+    fn end_run(&mut self) {
+        self.checkpoint = None;
+        SaveFile::store_checkpoint(None);
+
+        let cleared = self.current_level == self.levels.len();
+        let level = self.current_level.min(self.levels.len() - 1);
+        let (record, scores) = self
+            .run
+            .finish(level, cleared, self.world.time, self.world.score);
+        self.records.add_run(record);
+        self.records.save();
+
+        let scores: Vec<_> = scores
+            .into_iter()
+            .filter(|(key, score)| !self.run.tainted && self.records.qualifies(*key, *score))
+            .collect();
+        if scores.is_empty() {
+            self.screens = vec![Screen::Title];
+        } else {
+            let entry = NameEntry::new(&self.records.last_name, scores);
+            self.screens = vec![Screen::NameEntry(entry)];
+        }
+    }
+
+    fn name_entered(&mut self, result: NameEntryResult) {
+        if result == NameEntryResult::Stay {
+            return;
+        }
+        if let Some(Screen::NameEntry(entry)) = self.screens.pop() {
+            if result == NameEntryResult::Save {
+                let name = entry.name();
+                let date = Date::now();
+                for (key, score) in entry.scores {
+                    self.records.add_score(
+                        key,
+                        ScoreEntry {
+                            name: name.clone(),
+                            score,
+                            date,
+                        },
+                    );
+                }
+                self.records.last_name = name;
+                self.records.save();
+            }
+        }
+        self.screens = vec![Screen::Title];
+    }
+
+    /// `failure` names what could not be loaded, if anything. Once the last
+    /// download the player waits for is in, the loading screen makes way
+    /// for the title or the level it was covering, or for what is missing.
+    fn download_finished(&mut self, download: Download, failure: Option<String>) {
+        if !self.loading.finished(download, failure) || !self.loading.is_done() {
+            return;
+        }
+        if matches!(self.screens.last(), Some(Screen::Loading)) {
+            self.screens.pop();
+        }
+        if self.loading.failed_paths().next().is_some() {
+            self.screens
+                .push(Screen::Menu(Menu::new(MenuKind::LoadFailed)));
+        } else if self.screens.is_empty() {
+            self.screens.push(Screen::Title);
+        } else {
+            self.start();
+        }
+    }
+
+    /// Gets level `index` ready to play: unloads what neither it nor the
+    /// level after it needs, loads what it is missing behind the loading
+    /// screen and starts prefetching the next level.
+    fn stream_level(&mut self, index: usize) {
+        let mut needed = self.world.assets();
+        needed.merge(&self.sound_bank.assets());
+        needed.merge(&self.levels[index].assets);
+        let next = match self.levels.get(index + 1) {
+            Some(level) => level.assets.clone(),
+            None => AssetList::default(),
+        };
+
+        let mut keep = needed.clone();
+        keep.merge(&next);
+        self.texture_manager
+            .retain(|id| keep.textures.contains(&id));
+        self.audio_manager.retain(|id| keep.sounds.contains(&id));
+
+        for download in self.missing(&needed) {
+            if self.loading.is_pending(&download) {
+                self.loading.wait_for(&download);
+            } else {
+                self.download(download, true);
+            }
+        }
+        if !self.loading.is_done() && !matches!(self.screens.last(), Some(Screen::Loading)) {
+            self.screens.push(Screen::Loading);
+        }
+
+        for download in self.missing(&next) {
+            if !self.loading.is_pending(&download) {
+                self.download(download, false);
+            }
+        }
+    }
+
+    fn missing(&self, assets: &AssetList) -> Vec<Download> {
+        let textures = assets
+            .textures
+            .iter()
+            .filter(|it| !self.texture_manager.contains(**it))
+            .map(|it| Download::Texture(*it));
+        let sounds = assets
+            .sounds
+            .iter()
+            .filter(|it| !self.audio_manager.contains(**it))
+            .map(|it| Download::Sound(*it));
+        textures.chain(sounds).collect()
+    }
+
+    /// Volume goes up in steps of 10% and wraps around when confirmed at
+    /// 100%, so it can be changed with the confirm button alone.
+    fn step_volume(volume: f64, direction: f64) -> f64 {
+        let stepped = ((volume + direction * 0.1) * 10.0).round() / 10.0;
+        if stepped > 1.0 {
+            if direction > 0.0 && volume >= 1.0 {
+                0.0
+            } else {
+                1.0
+            }
+        } else {
+            stepped.max(0.0)
+        }
+    }
+
+    fn touch_button(&self, ctx: &Context<Self>, label: &str, action: Action) -> yew::Html {
+        let onclick = ctx.link().callback(move |e: TouchEvent| {
+            e.prevent_default();
+            Msg::TouchButton(action)
+        });
+        html! {
+            <button class="touch-button" ontouchstart={onclick}>{ label }</button>
+        }
+    }
+
+    /// Menus are tapped item by item, the other screens take a tap as
+    /// confirming.
+    fn gesture(&mut self, gesture: Gesture) {
+        let position = match gesture {
+            Gesture::Swipe(action) => {
+                self.handle_action(action);
+                return;
+            }
+            Gesture::Tap(position) => position,
+        };
+        let title_lines = match self.screens.last() {
+            Some(Screen::Menu(menu)) => self.menu_title(menu.kind).len(),
+            _ => {
+                self.handle_action(Action::Confirm);
+                return;
+            }
+        };
+        let point = self.canvas_point(position);
+        if let Some(Screen::Menu(menu)) = self.screens.last_mut() {
+            let kind = menu.kind;
+            let input = menu.tap(point, title_lines);
+            self.menu_input(kind, input);
+        }
+    }
+
+    /// `client` in CSS pixels of the viewport, as touches give it.
+    fn canvas_point(&self, client: Vector) -> Vector {
+        let canvas = self.canvas_ref.cast::<HtmlCanvasElement>().unwrap();
+        let rect = canvas.get_bounding_client_rect();
+        (client - Vector::new(rect.left(), rect.top())) * self.canvas_scale()
+    }
+
+    /// Canvas pixels per CSS pixel, the canvas is scaled to fit the screen.
+    fn canvas_scale(&self) -> f64 {
+        let canvas = self.canvas_ref.cast::<HtmlCanvasElement>().unwrap();
+        let width = canvas.get_bounding_client_rect().width();
+        if width > 0.0 {
+            canvas.width() as f64 / width
+        } else {
+            1.0
         }
     }
 
-    fn text(self, app: &App) -> Vec<String> {
-        match self {
-            GameOverKind::Lost => vec!["Press Enter to try again.".into()],
-            GameOverKind::Won => vec!["You have won!".into()],
-            GameOverKind::Loading => vec![
-                "Press Enter to start.".into(),
-                "Use arrows, space and left ctrl.".into(),
-            ],
-            GameOverKind::LevelFinished => vec![
-                format!("Level {} finished!", app.current_level),
-                "Press Enter to start next".to_string(),
-            ],
+    fn key_name(&self, action: Action) -> String {
+        match self.input.bindings.keys(action).first() {
+            Some(key) => key.clone(),
+            None => format!("<{}>", action.name()),
         }
     }
+
+    fn request_frame(&mut self, ctx: &Context<Self>) {
+        self._frame = Some({
+            let link = ctx.link().clone();
+            request_animation_frame(move |time| link.send_message(Msg::Timer(time)))
+        })
+    }
 }
@@ -0,0 +1,194 @@
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::music::Track;
+
+/// Every file the game can load, built into the binary so handles can be
+/// resolved before anything is downloaded.
+const MANIFEST: &str = include_str!("../resources/assets.json");
+
+/// An image from the manifest. Only `Assets` hands these out, so a handle
+/// always names an entry that exists. Stored handles are written as the
+/// entry's id and only read back if the manifest has it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TextureId(usize);
+
+thread_local! {
+    /// Texture ids in manifest order, to store handles by.
+    static TEXTURE_IDS: Vec<String> = Assets::load()
+        .textures
+        .into_iter()
+        .map(|it| it.id)
+        .collect();
+}
+
+impl Serialize for TextureId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TEXTURE_IDS.with(|ids| serializer.serialize_str(&ids[self.0]))
+    }
+}
+
+impl<'de> Deserialize<'de> for TextureId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        TEXTURE_IDS
+            .with(|ids| ids.iter().position(|it| *it == id))
+            .map(TextureId)
+            .ok_or_else(|| D::Error::custom(format!("no texture {} in the asset manifest", id)))
+    }
+}
+
+/// A sound effect or a piece of music from the manifest.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct SoundId(usize);
+
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetKind {
+    Texture,
+    Sound,
+    Music,
+}
+
+/// One line of `resources/assets.json`. The optional fields only mean
+/// something for some kinds of asset.
+#[derive(Deserialize)]
+pub struct AssetEntry {
+    pub id: String,
+    pub kind: AssetKind,
+    pub path: String,
+    /// Textures: the point sprites are positioned and rotated by, relative
+    /// to their size. Overrides the atlas.
+    #[serde(default)]
+    pub pivot: Option<[f64; 2]>,
+    /// Music: how loud the track is played at full music volume.
+    #[serde(default)]
+    pub volume: Option<f64>,
+    /// Music: loop points in seconds, see `Track`.
+    #[serde(default)]
+    pub loop_start: Option<f64>,
+    #[serde(default)]
+    pub loop_end: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    assets: Vec<AssetEntry>,
+}
+
+/// The parsed manifest. Ids are looked up once, when levels and the things
+/// that draw or play assets are created, and a typo fails right there
+/// instead of the first time the asset is needed.
+pub struct Assets {
+    textures: Vec<AssetEntry>,
+    sounds: Vec<AssetEntry>,
+}
+
+impl Assets {
+    pub fn load() -> Self {
+        let manifest: Manifest =
+            serde_json::from_str(MANIFEST).expect("resources/assets.json is malformed");
+        let (textures, sounds) = manifest
+            .assets
+            .into_iter()
+            .partition(|it| it.kind == AssetKind::Texture);
+        Self { textures, sounds }
+    }
+
+    pub fn texture(&self, id: &str) -> TextureId {
+        match self.textures.iter().position(|it| it.id == id) {
+            Some(index) => TextureId(index),
+            None => panic!("no texture {} in the asset manifest", id),
+        }
+    }
+
+    /// Sound effects and music alike.
+    pub fn sound(&self, id: &str) -> SoundId {
+        match self.sounds.iter().position(|it| it.id == id) {
+            Some(index) => SoundId(index),
+            None => panic!("no sound {} in the asset manifest", id),
+        }
+    }
+
+    /// A music track with the volume and loop points from the manifest.
+    pub fn track(&self, id: &str) -> Track {
+        let sound = self.sound(id);
+        let entry = self.sound_entry(sound);
+        let mut track = Track::new(sound);
+        if let Some(volume) = entry.volume {
+            track.volume = volume;
+        }
+        track.loop_start = entry.loop_start.unwrap_or(0.0);
+        track.loop_end = entry.loop_end;
+        track
+    }
+
+    pub fn texture_entry(&self, id: TextureId) -> &AssetEntry {
+        &self.textures[id.0]
+    }
+
+    pub fn sound_entry(&self, id: SoundId) -> &AssetEntry {
+        &self.sounds[id.0]
+    }
+
+    /// The texture stored at `path`, to match atlas frames to.
+    pub fn texture_at(&self, path: &str) -> Option<TextureId> {
+        self.textures
+            .iter()
+            .position(|it| it.path == path)
+            .map(TextureId)
+    }
+}
+
+/// The assets something needs loaded before it can be played.
+#[derive(Clone, Default)]
+pub struct AssetList {
+    pub textures: Vec<TextureId>,
+    pub sounds: Vec<SoundId>,
+}
+
+impl AssetList {
+    pub fn new(textures: Vec<TextureId>, sounds: Vec<SoundId>) -> Self {
+        Self { textures, sounds }
+    }
+
+    pub fn add_texture(&mut self, texture: TextureId) {
+        if !self.textures.contains(&texture) {
+            self.textures.push(texture);
+        }
+    }
+
+    pub fn add_sound(&mut self, sound: SoundId) {
+        if !self.sounds.contains(&sound) {
+            self.sounds.push(sound);
+        }
+    }
+
+    /// Adds what `other` needs, skipping what is already listed.
+    pub fn merge(&mut self, other: &AssetList) {
+        for texture in other.textures.iter() {
+            self.add_texture(*texture);
+        }
+        for sound in other.sounds.iter() {
+            self.add_sound(*sound);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stores_textures_by_their_id() {
+        let witch = Assets::load().texture("witch");
+        let json = serde_json::to_string(&witch).unwrap();
+        assert_eq!(json, r#""witch""#);
+        assert_eq!(serde_json::from_str::<TextureId>(&json).unwrap(), witch);
+    }
+
+    #[test]
+    fn refuses_textures_missing_from_the_manifest() {
+        assert!(serde_json::from_str::<TextureId>(r#""nothing""#).is_err());
+        assert!(serde_json::from_str::<TextureId>("0").is_err());
+    }
+}
@@ -0,0 +1,36 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Written by `tools/atlas-packer`. When it is missing the textures are
+/// downloaded one file at a time instead.
+pub const ATLAS_PATH: &str = "resources/atlas.json";
+
+/// A rectangle of the atlas image, in pixels.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Region {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+    /// The point the sprite is positioned and rotated by, relative to its
+    /// size. The middle of the frame by default.
+    #[serde(default = "Region::default_pivot")]
+    pub pivot: [f64; 2],
+}
+
+impl Region {
+    fn default_pivot() -> [f64; 2] {
+        [0.5, 0.5]
+    }
+}
+
+/// Frames are named after the file they were packed from, so
+/// `resources/ghost.png` finds the same picture with or without an atlas.
+/// Frames cut from a sprite sheet get their index appended, as in
+/// `resources/ghost.png#2`.
+#[derive(Serialize, Deserialize)]
+pub struct Atlas {
+    pub image: String,
+    pub frames: HashMap<String, Region>,
+}
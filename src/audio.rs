@@ -1,29 +1,58 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap};
 
 use wasm_bindgen::JsValue;
 use wasm_bindgen_futures::{spawn_local, JsFuture};
-use web_sys::HtmlAudioElement;
+use web_sys::{AudioContext, HtmlAudioElement, StereoPannerNode, Url};
+
+use crate::assets::SoundId;
 
 pub struct AudioManager {
-    audio: Vec<HtmlAudioElement>,
-    names: HashMap<String, usize>,
+    audio: HashMap<SoundId, HtmlAudioElement>,
+    // Created on first use: browsers only let an audio context run after the
+    // user has interacted with the page.
+    context: RefCell<Option<AudioContext>>,
+    panners: RefCell<HashMap<SoundId, StereoPannerNode>>,
 }
 
 impl AudioManager {
     pub fn new() -> Self {
         Self {
-            audio: vec![],
-            names: HashMap::new(),
+            audio: HashMap::new(),
+            context: RefCell::new(None),
+            panners: RefCell::new(HashMap::new()),
         }
     }
 
-    pub fn insert(&mut self, path: String, img: HtmlAudioElement) {
-        self.audio.push(img);
-        self.names.insert(path, self.audio.len() - 1);
+    pub fn insert(&mut self, id: SoundId, audio: HtmlAudioElement) {
+        self.audio.insert(id, audio);
+    }
+
+    pub fn contains(&self, id: SoundId) -> bool {
+        self.audio.contains_key(&id)
+    }
+
+    /// Unloads the sounds `keep` returns false for.
+    pub fn retain(&mut self, keep: impl Fn(SoundId) -> bool) {
+        self.audio.retain(|id, audio| {
+            if keep(*id) {
+                return true;
+            }
+            // Sounds are played from object URLs, see `download_audio`.
+            let _ = Url::revoke_object_url(&audio.src());
+            false
+        });
+        self.panners.borrow_mut().retain(|id, panner| {
+            if keep(*id) {
+                return true;
+            }
+            let _ = panner.disconnect();
+            false
+        });
     }
 
-    pub fn get(&self, path: &str) -> HtmlAudioElement {
-        self.audio[self.names[path]].clone()
+    /// `None` until the sound is loaded.
+    pub fn get(&self, id: SoundId) -> Option<HtmlAudioElement> {
+        self.audio.get(&id).cloned()
     }
 
     pub fn play(audio: HtmlAudioElement, set_loop: bool, force: bool, volume: f64) {
@@ -33,13 +62,47 @@ impl AudioManager {
         spawn_local(async move {
             audio.set_volume(volume);
             audio.set_loop(set_loop);
-            JsFuture::from(audio.play().unwrap()).await.unwrap_or(JsValue::UNDEFINED);
+            JsFuture::from(audio.play().unwrap())
+                .await
+                .unwrap_or(JsValue::UNDEFINED);
         });
     }
 
-    pub fn play_name(&self, path: &str, set_loop: bool, force: bool, volume: f64) {
-        let audio = self.get(path);
-        Self::play(audio, set_loop, force, volume);
+    /// Plays a sound effect from the start, `pan` goes from -1 (left) to 1
+    /// (right). The element is routed through a stereo panner on the first
+    /// call, so it should not be used for anything but panned playback.
+    pub fn play_panned(&self, id: SoundId, volume: f64, pan: f64) {
+        let audio = match self.get(id) {
+            Some(audio) => audio,
+            None => return,
+        };
+        if let Some(panner) = self.panner(id, &audio) {
+            panner.pan().set_value(pan as f32);
+        }
+        Self::play(audio, false, true, volume);
+    }
+
+    fn panner(&self, id: SoundId, audio: &HtmlAudioElement) -> Option<StereoPannerNode> {
+        if let Some(panner) = self.panners.borrow().get(&id) {
+            return Some(panner.clone());
+        }
+
+        let mut context = self.context.borrow_mut();
+        if context.is_none() {
+            *context = AudioContext::new().ok();
+        }
+        let context = context.as_ref()?;
+        let _ = context.resume();
+
+        let source = context.create_media_element_source(audio).ok()?;
+        let panner = context.create_stereo_panner().ok()?;
+        source.connect_with_audio_node(&panner).ok()?;
+        panner
+            .connect_with_audio_node(&context.destination())
+            .ok()?;
+
+        self.panners.borrow_mut().insert(id, panner.clone());
+        Some(panner)
     }
 
     pub fn stop(audio: &HtmlAudioElement) {
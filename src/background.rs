@@ -0,0 +1,157 @@
+use crate::{
+    assets::TextureId,
+    geometry::Vector,
+    render::{BlendMode, Color, Frame, Layer, Sprite},
+    textures::TextureManager,
+};
+
+/// How quickly a change of scroll speed between scenes is eased in, per
+/// second.
+const SCROLL_EASING: f64 = 1.5;
+
+/// How a layer's image covers the playfield. Tiles are as wide as the
+/// playfield, keeping the image's proportions.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Tiling {
+    /// Repeats along both axes, for layers scrolling in any direction.
+    Both,
+    /// A single column of tiles, for layers scrolling straight down or up.
+    Vertical,
+    /// Covers the playfield once and doesn't scroll.
+    Stretch,
+}
+
+#[derive(Clone)]
+pub struct BackgroundLayer {
+    texture: TextureId,
+    /// Pixels per second at a scroll speed of `1.0`.
+    speed: f64,
+    direction: Vector,
+    tiling: Tiling,
+    opacity: f64,
+    blend: BlendMode,
+    offset: Vector,
+}
+
+impl BackgroundLayer {
+    /// A layer scrolling down, the way the player flies.
+    pub fn new(texture: TextureId, speed: f64) -> Self {
+        Self {
+            texture,
+            speed,
+            direction: Vector::new(0.0, 1.0),
+            tiling: Tiling::Both,
+            opacity: 1.0,
+            blend: BlendMode::Normal,
+            offset: Vector::zero(),
+        }
+    }
+
+    pub fn with_direction(mut self, direction: Vector) -> Self {
+        self.direction = direction.norm();
+        self
+    }
+
+    pub fn with_tiling(mut self, tiling: Tiling) -> Self {
+        self.tiling = tiling;
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: f64) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    pub fn with_blend(mut self, blend: BlendMode) -> Self {
+        self.blend = blend;
+        self
+    }
+
+    fn draw(&self, frame: &mut Frame, textures: &TextureManager, top_left: Vector, size: Vector) {
+        let img = match textures.region(self.texture) {
+            Some(img) => img,
+            None => return,
+        };
+        let sprite = |position: Vector, tile: Vector| {
+            Sprite::new(&img, position + tile * 0.5, tile)
+                .with_alpha(self.opacity)
+                .with_blend(self.blend)
+        };
+
+        if self.tiling == Tiling::Stretch {
+            frame.sprite(Layer::Background, sprite(top_left, size));
+            return;
+        }
+
+        let tile = Vector::new(size.x, size.x * img.size.y / img.size.x);
+        let (columns, x) = match self.tiling {
+            Tiling::Both => (
+                tiles(size.x, tile.x),
+                self.offset.x.rem_euclid(tile.x) - tile.x,
+            ),
+            _ => (1, 0.0),
+        };
+        let rows = tiles(size.y, tile.y);
+        let y = self.offset.y.rem_euclid(tile.y) - tile.y;
+        for row in 0..rows {
+            for column in 0..columns {
+                let position =
+                    top_left + Vector::new(x + column as f64 * tile.x, y + row as f64 * tile.y);
+                frame.sprite(Layer::Background, sprite(position, tile));
+            }
+        }
+    }
+}
+
+/// Tiles needed to cover `length` while scrolled by up to one tile.
+fn tiles(length: f64, tile: f64) -> usize {
+    (length / tile).ceil() as usize + 1
+}
+
+/// What is behind the playfield: a colour, which is all that shows where
+/// images are missing, and image layers drawn over it back to front.
+#[derive(Clone)]
+pub struct Background {
+    color: Color,
+    layers: Vec<BackgroundLayer>,
+    scroll: f64,
+}
+
+impl Background {
+    pub fn new(color: Color) -> Self {
+        Self {
+            color,
+            layers: vec![],
+            scroll: 1.0,
+        }
+    }
+
+    pub fn with_layer(mut self, layer: BackgroundLayer) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// `scroll` multiplies every layer's speed, changes to it are eased in.
+    pub fn tick(&mut self, delta: f64, scroll: f64) {
+        self.scroll += (scroll - self.scroll) * (SCROLL_EASING * delta).min(1.0);
+        for layer in self.layers.iter_mut() {
+            if layer.tiling != Tiling::Stretch {
+                layer.offset += layer.direction * (layer.speed * self.scroll * delta);
+            }
+        }
+    }
+
+    /// Covers the playfield, `size` big from `top_left` on the canvas.
+    pub fn draw(
+        &self,
+        frame: &mut Frame,
+        textures: &TextureManager,
+        top_left: Vector,
+        size: Vector,
+    ) {
+        frame.rect(Layer::Background, top_left, size, self.color);
+        for layer in self.layers.iter() {
+            layer.draw(frame, textures, top_left, size);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    geometry::Vector,
+    input::{Action, Bindings, Input},
+    render::{Color, Frame, Layer, CANVAS_WIDTH},
+    screens::fill,
+};
+
+/// The rebinding screen. It is navigated with fixed keys (arrows, Enter,
+/// Backspace, Escape) so that a broken binding can always be fixed. Gamepad
+/// buttons are not rebindable here, only the stick mode can be switched.
+pub struct ControlsScreen {
+    selected: usize,
+    waiting_for_key: bool,
+    message: Option<String>,
+}
+
+#[derive(PartialEq)]
+pub enum ControlsResult {
+    Stay,
+    Close,
+}
+
+impl ControlsScreen {
+    pub fn new() -> Self {
+        Self {
+            selected: 0,
+            waiting_for_key: false,
+            message: None,
+        }
+    }
+
+    pub fn key_down(&mut self, key: &str, input: &mut Input) -> ControlsResult {
+        let bindings = &mut input.bindings;
+        let action = Action::ALL[self.selected];
+
+        if self.waiting_for_key {
+            self.waiting_for_key = false;
+            if key == "Escape" {
+                self.message = None;
+                return ControlsResult::Stay;
+            }
+            bindings.bind(action, key);
+            self.message = bindings
+                .conflicts(action)
+                .iter()
+                .find(|(it, _)| it == key)
+                .map(|(_, other)| format!("{} is also bound to {}", key, other.name()));
+            return ControlsResult::Stay;
+        }
+
+        self.message = None;
+        match key {
+            "ArrowUp" => {
+                self.selected = (self.selected + Action::ALL.len() - 1) % Action::ALL.len()
+            }
+            "ArrowDown" => self.selected = (self.selected + 1) % Action::ALL.len(),
+            "Enter" => self.waiting_for_key = true,
+            "Backspace" | "Delete" => bindings.clear(action),
+            "KeyR" => *bindings = Bindings::default(),
+            "KeyG" => input.gamepad.config.digital = !input.gamepad.config.digital,
+            "Escape" => {
+                self.selected = 0;
+                return ControlsResult::Close;
+            }
+            _ => (),
+        }
+        ControlsResult::Stay
+    }
+
+    pub fn draw(&self, frame: &mut Frame, input: &Input) {
+        let bindings = &input.bindings;
+        fill(frame, Color::rgb(60, 60, 120));
+
+        frame.text(
+            Layer::Ui,
+            "Controls",
+            Vector::new(CANVAS_WIDTH / 2.0, 100.0),
+            "48px bold",
+            Color::WHITE,
+        );
+
+        let mut top = 200.0;
+        for (i, action) in Action::ALL.iter().enumerate() {
+            let keys = if self.waiting_for_key && i == self.selected {
+                "press a key...".to_string()
+            } else {
+                bindings.keys(*action).join(", ")
+            };
+            let color = if !bindings.conflicts(*action).is_empty() {
+                Color::rgb(255, 120, 120)
+            } else if i == self.selected {
+                Color::YELLOW
+            } else {
+                Color::WHITE
+            };
+            let marker = if i == self.selected { "> " } else { "" };
+            frame.text(
+                Layer::Ui,
+                format!("{}{}: {}", marker, action.name(), keys),
+                Vector::new(CANVAS_WIDTH / 2.0, top),
+                "28px bold",
+                color,
+            );
+            top += 50.0;
+        }
+
+        if let Some(message) = &self.message {
+            frame.text(
+                Layer::Ui,
+                message.as_str(),
+                Vector::new(CANVAS_WIDTH / 2.0, top + 30.0),
+                "22px bold",
+                Color::WHITE,
+            );
+        }
+        let stick_mode = if input.gamepad.config.digital {
+            "G: gamepad stick is 8-way"
+        } else {
+            "G: gamepad stick is analog"
+        };
+        for line in [
+            "Enter: add key, Backspace: clear",
+            "R: reset to defaults, Escape: back",
+            stick_mode,
+        ] {
+            top += 40.0;
+            frame.text(
+                Layer::Ui,
+                line,
+                Vector::new(CANVAS_WIDTH / 2.0, top + 60.0),
+                "22px bold",
+                Color::WHITE,
+            );
+        }
+    }
+}
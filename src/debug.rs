@@ -0,0 +1,176 @@
+use crate::{
+    enemies::Enemy,
+    geometry::Vector,
+    hud::PANEL_LEFT,
+    level::Level,
+    render::{Color, Frame, Layer, CANVAS_WIDTH},
+    world::World,
+};
+
+const TOGGLE_KEY: &str = "Backquote";
+
+/// How long a single step lasts while the game is frozen.
+const STEP_TIME: f64 = 1.0 / 60.0;
+
+const MIN_TIME_SCALE: f64 = 0.125;
+const MAX_TIME_SCALE: f64 = 4.0;
+
+/// Enemies listed in the panel, more would run past its bottom.
+const LISTED_ENEMIES: usize = 5;
+
+const TEXT_COLOR: Color = Color::rgb(255, 255, 150);
+
+/// Developer tools over the playfield. `` ` `` shows the overlay, the rest
+/// of the hotkeys only work while it is shown:
+///
+/// - `-` / `=` halve and double the speed of time
+/// - `.` freezes the game and steps it one frame, `0` resumes at full speed
+/// - `N` skips to the next scene
+/// - `I` toggles invincibility
+/// - `[` / `]` pick an enemy from any level's scenes, `E` spawns it
+///
+/// They also make the snapshot actions work outside of practice.
+pub struct DebugTools {
+    overlay: bool,
+    time_scale: f64,
+    frozen: bool,
+    steps: usize,
+    catalogue: Vec<(String, Enemy)>,
+    selected: usize,
+    /// A hotkey changed the world since `tampered` was last asked.
+    tampered: bool,
+}
+
+impl DebugTools {
+    pub fn new(levels: &[Level]) -> Self {
+        let mut catalogue = vec![];
+        for (l, level) in levels.iter().enumerate() {
+            for (s, scene) in level.scenes.iter().enumerate() {
+                for (e, enemy) in scene.enemies().iter().enumerate() {
+                    catalogue.push((format!("L{} S{} #{}", l + 1, s + 1, e + 1), enemy.clone()));
+                }
+            }
+        }
+        Self {
+            overlay: false,
+            time_scale: 1.0,
+            frozen: false,
+            steps: 0,
+            catalogue,
+            selected: 0,
+            tampered: false,
+        }
+    }
+
+    /// Handles a hotkey, returns whether `key` was one.
+    pub fn key_down(&mut self, key: &str, world: &mut World) -> bool {
+        if key == TOGGLE_KEY {
+            self.overlay = !self.overlay;
+            return true;
+        }
+        if !self.overlay {
+            return false;
+        }
+        self.tampered |= matches!(key, "Minus" | "Equal" | "Period" | "KeyN" | "KeyI" | "KeyE");
+        match key {
+            "Minus" => self.time_scale = (self.time_scale / 2.0).max(MIN_TIME_SCALE),
+            "Equal" => self.time_scale = (self.time_scale * 2.0).min(MAX_TIME_SCALE),
+            "Period" => {
+                self.frozen = true;
+                self.steps += 1;
+            }
+            "Digit0" => {
+                self.frozen = false;
+                self.steps = 0;
+                self.time_scale = 1.0;
+            }
+            "KeyN" => world.skip_scene(),
+            "KeyI" => world.invincible = !world.invincible,
+            "BracketLeft" if !self.catalogue.is_empty() => {
+                self.selected = (self.selected + self.catalogue.len() - 1) % self.catalogue.len();
+            }
+            "BracketRight" if !self.catalogue.is_empty() => {
+                self.selected = (self.selected + 1) % self.catalogue.len();
+            }
+            "KeyE" => {
+                if let Some((_, enemy)) = self.catalogue.get(self.selected) {
+                    world.spawn(enemy.clone());
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Whether the world plays differently than it would without the tools,
+    /// now or since the last call.
+    pub fn tampered(&mut self, world: &World) -> bool {
+        std::mem::take(&mut self.tampered)
+            || self.time_scale != 1.0
+            || self.frozen
+            || world.invincible
+    }
+
+    /// How far to tick the world this frame, if at all.
+    pub fn world_delta(&mut self, delta: f64) -> Option<f64> {
+        if !self.frozen {
+            return Some(delta * self.time_scale);
+        }
+        if self.steps > 0 {
+            self.steps -= 1;
+            return Some(STEP_TIME);
+        }
+        None
+    }
+
+    /// Fills the bottom of the side panel, under what the HUD shows.
+    pub fn draw(&self, frame: &mut Frame, world: &World) {
+        if !self.overlay {
+            return;
+        }
+        world.draw_debug(frame);
+
+        let speed = if self.frozen {
+            "frozen".to_string()
+        } else {
+            format!("x{}", self.time_scale)
+        };
+        let spawn = match self.catalogue.get(self.selected) {
+            Some((name, _)) => name.as_str(),
+            None => "-",
+        };
+        let mut lines = vec![
+            format!("Speed: {}", speed),
+            format!("Bullets: {}", world.bullet_count()),
+            format!("Rank: {:.2}", world.rank.value()),
+            format!(
+                "Invincible: {}",
+                if world.invincible { "on" } else { "off" }
+            ),
+            format!("Spawn: {}", spawn),
+        ];
+        for (i, enemy) in world.enemies().iter().take(LISTED_ENEMIES).enumerate() {
+            let (time, length) = enemy.phase_time();
+            lines.push(format!(
+                "#{} phase {} {:.1}/{:.1}s",
+                i + 1,
+                enemy.phase(),
+                time,
+                length
+            ));
+        }
+
+        let x = (PANEL_LEFT + CANVAS_WIDTH) / 2.0;
+        let mut top = 610.0;
+        for line in lines {
+            frame.text(
+                Layer::Ui,
+                line,
+                Vector::new(x, top),
+                "22px bold",
+                TEXT_COLOR,
+            );
+            top += 32.0;
+        }
+    }
+}
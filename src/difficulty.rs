@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+/// How hard a run is. Emitters are scaled by it when a level is built for a
+/// run, and phases and emitters can be left out below some difficulty.
+/// Scores are only compared between runs of the same difficulty.
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Serialize, Deserialize,
+)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Lunatic,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Lunatic,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Lunatic => "Lunatic",
+        }
+    }
+
+    /// The next one in `ALL`, or the previous one for a negative `direction`,
+    /// wrapping around.
+    pub fn step(self, direction: f64) -> Self {
+        let index = Self::ALL.iter().position(|it| *it == self).unwrap_or(0);
+        let len = Self::ALL.len();
+        if direction < 0.0 {
+            Self::ALL[(index + len - 1) % len]
+        } else {
+            Self::ALL[(index + 1) % len]
+        }
+    }
+
+    /// Multiplies how fast bullets fly.
+    pub fn bullet_speed(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.8,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.15,
+            Difficulty::Lunatic => 1.3,
+        }
+    }
+
+    /// Multiplies the time between volleys.
+    pub fn cooldown(self) -> f64 {
+        match self {
+            Difficulty::Easy => 1.4,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.85,
+            Difficulty::Lunatic => 0.7,
+        }
+    }
+
+    /// How many bullets a volley of `bullets` has. Volleys of two or more
+    /// keep at least two, spreads need both ends.
+    pub fn density(self, bullets: usize) -> usize {
+        let factor = match self {
+            Difficulty::Easy => 0.6,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.4,
+            Difficulty::Lunatic => 1.8,
+        };
+        ((bullets as f64 * factor).round() as usize).max(bullets.min(2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn density_keeps_small_volleys() {
+        for difficulty in Difficulty::ALL {
+            assert_eq!(difficulty.density(0), 0);
+            assert!(difficulty.density(1) >= 1);
+            assert!(difficulty.density(2) >= 2);
+        }
+        assert_eq!(Difficulty::Easy.density(1), 1);
+        assert_eq!(Difficulty::Easy.density(2), 2);
+        assert_eq!(Difficulty::Lunatic.density(1), 2);
+        assert_eq!(Difficulty::Lunatic.density(2), 4);
+    }
+}
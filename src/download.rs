@@ -1,67 +1,304 @@
-use futures::channel::oneshot;
+use std::{cell::RefCell, collections::HashMap, future::Future, rc::Rc};
+
+use futures::{
+    channel::oneshot,
+    future::{select, Either},
+};
+use gloo::timers::callback::Timeout;
 use gloo_net::http::Request;
 use wasm_bindgen::{prelude::Closure, JsCast};
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{HtmlAudioElement, HtmlImageElement, Url};
 
+use crate::{
+    assets::{SoundId, TextureId},
+    atlas::Atlas,
+};
+
+/// How long one attempt at a file may take before it counts as failed.
+const TIMEOUT_MS: u32 = 15_000;
+/// Tries per file. The wait between tries doubles every time, starting at
+/// `RETRY_DELAY_MS`.
+const ATTEMPTS: u32 = 3;
+const RETRY_DELAY_MS: u32 = 500;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub enum Download {
-    Audio(String),
-    Image(String),
+    Sound(SoundId),
+    Texture(TextureId),
+    Atlas(String),
 }
 
-pub async fn download_image(path: &str) -> HtmlImageElement {
-    let resp = Request::get(path).send().await.unwrap();
-    let blob = JsFuture::from(resp.as_raw().blob().unwrap()).await.unwrap();
+/// Counts the downloads the player is waiting for, for the loading screen,
+/// and keeps the ones that failed for good, to be named on the error screen
+/// and tried again. Downloads for later levels run in the background and
+/// are only counted once the player has to wait for them.
+#[derive(Default)]
+pub struct LoadProgress {
+    total: usize,
+    finished: usize,
+    failed: Vec<(Download, String)>,
+    /// Downloads under way, and whether the player is waiting for them.
+    pending: HashMap<Download, bool>,
+}
 
-    let url = Url::create_object_url_with_blob(&blob.unchecked_into()).unwrap();
-    let image = HtmlImageElement::new().unwrap();
+impl LoadProgress {
+    /// Counting starts over with the first download after the loading
+    /// screen was left.
+    pub fn started(&mut self, download: Download, waiting: bool) {
+        if waiting {
+            self.count();
+        }
+        self.pending.insert(download, waiting);
+    }
 
-    let (send, recv) = oneshot::channel();
+    fn count(&mut self) {
+        if self.is_done() {
+            self.total = 0;
+            self.finished = 0;
+        }
+        self.total += 1;
+    }
 
-    let on_load_closure = Closure::once(Box::new(move || {
-        send.send(()).unwrap();
-    }) as Box<dyn FnOnce()>);
-    image.set_onload(Some(on_load_closure.as_ref().unchecked_ref()));
-    on_load_closure.forget();
+    pub fn is_pending(&self, download: &Download) -> bool {
+        self.pending.contains_key(download)
+    }
 
-    let cloned_path = path.to_string();
-    let on_error_closure = Closure::wrap(Box::new(move || {
-        panic!("image {} loading failed", cloned_path);
-    }) as Box<dyn FnMut()>);
-    image.set_onerror(Some(on_error_closure.as_ref().unchecked_ref()));
-    on_error_closure.forget();
+    /// Makes the player wait for a download running in the background.
+    pub fn wait_for(&mut self, download: &Download) {
+        if let Some(waiting) = self.pending.get(download).copied() {
+            if !waiting {
+                self.count();
+                self.pending.insert(download.clone(), true);
+            }
+        }
+    }
 
-    image.set_src(&url);
+    /// `failure` is the path that could not be loaded. Returns whether the
+    /// player was waiting for the download, background failures are only
+    /// logged and the asset is tried again when it is needed.
+    pub fn finished(&mut self, download: Download, failure: Option<String>) -> bool {
+        if !self.pending.remove(&download).unwrap_or(false) {
+            if let Some(path) = failure {
+                log::warn!("Prefetching {} failed", path);
+            }
+            return false;
+        }
+        self.finished += 1;
+        if let Some(path) = failure {
+            self.failed.push((download, path));
+        }
+        true
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.finished == self.total
+    }
+
+    pub fn counts(&self) -> (usize, usize) {
+        (self.finished, self.total)
+    }
 
-    recv.await.unwrap();
+    pub fn failed_paths(&self) -> impl Iterator<Item = &str> {
+        self.failed.iter().map(|(_, path)| path.as_str())
+    }
+
+    /// Forgets the failures and hands back their downloads to start again.
+    pub fn retry(&mut self) -> Vec<Download> {
+        std::mem::take(&mut self.failed)
+            .into_iter()
+            .map(|(download, _)| download)
+            .collect()
+    }
+}
+
+/// Runs `attempt` until it succeeds, at most `ATTEMPTS` times, each under a
+/// timeout. The error is the one from the last try.
+async fn with_retries<T, F, Fut>(path: &str, attempt: F) -> Result<T, String>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+{
+    let mut delay = RETRY_DELAY_MS;
+    let mut tries = 1;
+    loop {
+        let error = match with_timeout(attempt()).await {
+            Ok(value) => return Ok(value),
+            Err(e) => e,
+        };
+        if tries == ATTEMPTS {
+            log::error!("Giving up on {}: {}", path, error);
+            return Err(error);
+        }
+        log::warn!("Loading {} failed, retrying: {}", path, error);
+        sleep(delay).await;
+        delay *= 2;
+        tries += 1;
+    }
+}
+
+async fn with_timeout<T>(future: impl Future<Output = Result<T, String>>) -> Result<T, String> {
+    match select(Box::pin(future), Box::pin(sleep(TIMEOUT_MS))).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => Err(format!("timed out after {} s", TIMEOUT_MS / 1000)),
+    }
+}
+
+async fn sleep(ms: u32) {
+    let (send, recv) = oneshot::channel();
+    let _timeout = Timeout::new(ms, move || {
+        let _ = send.send(());
+    });
+    let _ = recv.await;
+}
 
-    image
+/// An element's `onload`-style handler and its `onerror`. They are taken
+/// off the element again when this is dropped, finished or timed out, so
+/// the closures can go with it.
+struct ElementHandlers<L, E>
+where
+    L: Fn(Option<&js_sys::Function>),
+    E: Fn(Option<&js_sys::Function>),
+{
+    set_load: L,
+    set_error: E,
+    _on_load: Closure<dyn FnMut()>,
+    _on_error: Closure<dyn FnMut()>,
 }
 
-pub async fn download_audio(path: &str) -> HtmlAudioElement {
-    log::info!("download_audio {}", path);
-    let audio = HtmlAudioElement::new().unwrap();
+impl<L, E> Drop for ElementHandlers<L, E>
+where
+    L: Fn(Option<&js_sys::Function>),
+    E: Fn(Option<&js_sys::Function>),
+{
+    fn drop(&mut self) {
+        (self.set_load)(None);
+        (self.set_error)(None);
+    }
+}
 
+/// Hooks up an element's `onload`-style event and its `onerror`, the
+/// future resolves on whichever fires first.
+fn wait_for_element(
+    set_load: impl Fn(Option<&js_sys::Function>),
+    set_error: impl Fn(Option<&js_sys::Function>),
+) -> impl Future<Output = Result<(), String>> {
     let (send, recv) = oneshot::channel();
+    let send = Rc::new(RefCell::new(Some(send)));
 
-    let cloned_path = path.to_string();
-    let on_load_closure = Closure::once(Box::new(move || {
-        log::info!("FINISHED download_audio {}", cloned_path);
-        send.send(()).unwrap();
+    let on_load_send = send.clone();
+    let on_load = Closure::once(Box::new(move || {
+        if let Some(send) = on_load_send.borrow_mut().take() {
+            let _ = send.send(Ok(()));
+        }
     }) as Box<dyn FnOnce()>);
-    audio.set_onloadeddata(Some(on_load_closure.as_ref().unchecked_ref()));
-    on_load_closure.forget();
+    set_load(Some(on_load.as_ref().unchecked_ref()));
 
-    let cloned_path = path.to_string();
-    let on_error_closure = Closure::wrap(Box::new(move || {
-        panic!("audio {} loading failed", cloned_path);
-    }) as Box<dyn FnMut()>);
-    audio.set_onerror(Some(on_error_closure.as_ref().unchecked_ref()));
-    on_error_closure.forget();
+    let on_error = Closure::once(Box::new(move || {
+        if let Some(send) = send.borrow_mut().take() {
+            let _ = send.send(Err("the file could not be decoded".to_string()));
+        }
+    }) as Box<dyn FnOnce()>);
+    set_error(Some(on_error.as_ref().unchecked_ref()));
+
+    let handlers = ElementHandlers {
+        set_load,
+        set_error,
+        _on_load: on_load,
+        _on_error: on_error,
+    };
+    async move {
+        let result = recv
+            .await
+            .unwrap_or_else(|_| Err("loading was cancelled".to_string()));
+        drop(handlers);
+        result
+    }
+}
 
-    audio.set_src(path);
+pub async fn download_image(path: &str) -> Result<HtmlImageElement, String> {
+    with_retries(path, || try_download_image(path)).await
+}
+
+async fn try_download_image(path: &str) -> Result<HtmlImageElement, String> {
+    let url = download_blob(path).await?;
+    let image = HtmlImageElement::new().map_err(|_| "could not create an image".to_string())?;
+    let loaded = wait_for_element(|it| image.set_onload(it), |it| image.set_onerror(it));
+    image.set_src(&url);
+    let result = loaded.await;
+    let _ = Url::revoke_object_url(&url);
+    result.map(|_| image)
+}
 
-    recv.await.unwrap();
+/// Fetches a whole file into an object URL. Going through `fetch` instead
+/// of pointing an element at the path lets the service worker answer from
+/// its cache when offline, media elements ask for byte ranges it can't.
+async fn download_blob(path: &str) -> Result<String, String> {
+    let resp = Request::get(path).send().await.map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(format!("HTTP {}", resp.status()));
+    }
+    let blob = resp.as_raw().blob().map_err(|_| "no body".to_string())?;
+    let blob = JsFuture::from(blob)
+        .await
+        .map_err(|_| "the download was interrupted".to_string())?;
+
+    Url::create_object_url_with_blob(&blob.unchecked_into())
+        .map_err(|_| "could not read the file".to_string())
+}
+
+/// `None` when there is no atlas, the loose images are used then.
+pub async fn download_atlas(path: &str) -> Option<(Atlas, HtmlImageElement)> {
+    let atlas = match with_retries(path, || try_download_atlas(path)).await {
+        Ok(Some(atlas)) => atlas,
+        Ok(None) => return None,
+        Err(_) => {
+            log::warn!("Loading images one by one instead of the atlas");
+            return None;
+        }
+    };
+    // The loose images are still there when the atlas image isn't.
+    let image = download_image(&atlas.image).await.ok()?;
+    Some((atlas, image))
+}
+
+/// Only errors that trying again could fix are errors, an atlas that isn't
+/// there or can't be read is `None`.
+async fn try_download_atlas(path: &str) -> Result<Option<Atlas>, String> {
+    let resp = Request::get(path).send().await.map_err(|e| e.to_string())?;
+    if resp.status() == 404 {
+        log::info!("No atlas at {}, loading images one by one", path);
+        return Ok(None);
+    }
+    if !resp.ok() {
+        return Err(format!("HTTP {}", resp.status()));
+    }
+    let text = resp
+        .text()
+        .await
+        .map_err(|_| "the download was interrupted".to_string())?;
+    match serde_json::from_str(&text) {
+        Ok(atlas) => Ok(Some(atlas)),
+        Err(e) => {
+            log::error!("Failed to parse atlas {}: {}", path, e);
+            Ok(None)
+        }
+    }
+}
+
+pub async fn download_audio(path: &str) -> Result<HtmlAudioElement, String> {
+    with_retries(path, || try_download_audio(path)).await
+}
 
-    audio
+/// The object URL is kept, the element reads from it whenever it plays.
+async fn try_download_audio(path: &str) -> Result<HtmlAudioElement, String> {
+    let url = download_blob(path).await?;
+    let audio = HtmlAudioElement::new().map_err(|_| "could not create audio".to_string())?;
+    let loaded = wait_for_element(|it| audio.set_onloadeddata(it), |it| audio.set_onerror(it));
+    audio.set_src(&url);
+    let result = loaded.await;
+    if result.is_err() {
+        let _ = Url::revoke_object_url(&url);
+    }
+    result.map(|_| audio)
 }
@@ -1,10 +1,37 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
+    difficulty::Difficulty,
     geometry::{Circle, Vector},
-    world::World,
+    rank::Rank,
+    world::{BulletAnimation, World},
 };
 
 use super::BulletEmmiter;
 
+/// Every kind of emitter, with what it is made of.
+#[derive(Serialize, Deserialize)]
+pub enum EmitterData {
+    Combinator(Box<EmitterData>, Box<EmitterData>),
+    Circle(CircleEmitter),
+    Forward(ForwardEmitter),
+    Hardcoded(HardcodedEmitter),
+}
+
+impl EmitterData {
+    pub fn into_emitter(self) -> Box<dyn BulletEmmiter> {
+        match self {
+            EmitterData::Combinator(first, second) => Box::new(CombinatorEmitter::new(
+                first.into_emitter(),
+                second.into_emitter(),
+            )),
+            EmitterData::Circle(it) => Box::new(it),
+            EmitterData::Forward(it) => Box::new(it),
+            EmitterData::Hardcoded(it) => Box::new(it),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct CombinatorEmitter<First, Second>(First, Second);
 
@@ -22,19 +49,36 @@ impl<First: BulletEmmiter + Clone, Second: BulletEmmiter + Clone> BulletEmmiter
         enemy: &Circle,
         time: f64,
         delta: f64,
+        rank: Rank,
         bullets: &mut Vec<crate::world::Bullet>,
     ) {
-        self.0.tick(enemy, time, delta, bullets);
-        self.1.tick(enemy, time, delta, bullets);
+        self.0.tick(enemy, time, delta, rank, bullets);
+        self.1.tick(enemy, time, delta, rank, bullets);
+    }
+
+    fn origins(&self, enemy: &Circle) -> Vec<Vector> {
+        let mut origins = self.0.origins(enemy);
+        origins.extend(self.1.origins(enemy));
+        origins
+    }
+
+    fn data(&self) -> EmitterData {
+        EmitterData::Combinator(Box::new(self.0.data()), Box::new(self.1.data()))
+    }
+
+    fn scale(&mut self, difficulty: Difficulty) {
+        self.0.scale(difficulty);
+        self.1.scale(difficulty);
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CircleEmitter {
     timer: f64,
     bullet_cooldown: f64,
     bullet_number: usize,
     bullet_speed: f64,
+    animation: BulletAnimation,
 }
 
 impl CircleEmitter {
@@ -44,8 +88,14 @@ impl CircleEmitter {
             bullet_cooldown: cooldown,
             bullet_number: bullets,
             bullet_speed: speed,
+            animation: BulletAnimation::Directional,
         }
     }
+
+    pub fn with_animation(mut self, animation: BulletAnimation) -> Self {
+        self.animation = animation;
+        self
+    }
 }
 
 impl BulletEmmiter for CircleEmitter {
@@ -54,6 +104,7 @@ impl BulletEmmiter for CircleEmitter {
         enemy: &Circle,
         time: f64,
         delta: f64,
+        rank: Rank,
         bullets: &mut Vec<crate::world::Bullet>,
     ) {
         self.timer -= delta;
@@ -63,24 +114,34 @@ impl BulletEmmiter for CircleEmitter {
 
         let mut angle = 0.0;
 
-        self.timer += self.bullet_cooldown;
-        let d_angle = std::f64::consts::TAU / self.bullet_number as f64;
+        self.timer += self.bullet_cooldown * rank.cooldown();
+        let d_angle = std::f64::consts::TAU / rank.density(self.bullet_number) as f64;
 
         while angle <= std::f64::consts::TAU {
             let t = time + angle;
             let speed_direction = Vector::new(t.sin(), t.cos());
-            let speed = speed_direction * self.bullet_speed;
+            let speed = speed_direction * (self.bullet_speed * rank.bullet_speed());
 
-            bullets.push(World::new_bullet(
-                enemy.coord + speed_direction * enemy.r,
-                speed,
-            ));
+            bullets.push(
+                World::new_bullet(enemy.coord + speed_direction * enemy.r, speed)
+                    .with_animation(self.animation),
+            );
             angle += d_angle;
         }
     }
+
+    fn data(&self) -> EmitterData {
+        EmitterData::Circle(self.clone())
+    }
+
+    fn scale(&mut self, difficulty: Difficulty) {
+        self.bullet_cooldown *= difficulty.cooldown();
+        self.bullet_number = difficulty.density(self.bullet_number);
+        self.bullet_speed *= difficulty.bullet_speed();
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ForwardEmitter {
     timer: f64,
     bullet_cooldown: f64,
@@ -88,6 +149,7 @@ pub struct ForwardEmitter {
     bullet_speed: f64,
     forward: Vector,
     spawn_angle: f64,
+    animation: BulletAnimation,
 }
 
 impl ForwardEmitter {
@@ -99,8 +161,14 @@ impl ForwardEmitter {
             bullet_speed: forward.len(),
             forward: forward.norm(),
             spawn_angle,
+            animation: BulletAnimation::Directional,
         }
     }
+
+    pub fn with_animation(mut self, animation: BulletAnimation) -> Self {
+        self.animation = animation;
+        self
+    }
 }
 
 impl BulletEmmiter for ForwardEmitter {
@@ -109,6 +177,7 @@ impl BulletEmmiter for ForwardEmitter {
         enemy: &Circle,
         _time: f64,
         delta: f64,
+        rank: Rank,
         bullets: &mut Vec<crate::world::Bullet>,
     ) {
         self.timer -= delta;
@@ -119,23 +188,33 @@ impl BulletEmmiter for ForwardEmitter {
         let mut angle = self.forward.angle() - self.spawn_angle / 2.0;
         let end_angle = self.forward.angle() + self.spawn_angle / 2.0;
 
-        self.timer += self.bullet_cooldown;
-        let d_angle = self.spawn_angle / (self.bullet_number - 1) as f64;
+        self.timer += self.bullet_cooldown * rank.cooldown();
+        let d_angle = self.spawn_angle / (rank.density(self.bullet_number) - 1) as f64;
 
         while angle <= end_angle {
             let speed_direction = Vector::new(angle.cos(), angle.sin());
-            let speed = speed_direction * self.bullet_speed;
+            let speed = speed_direction * (self.bullet_speed * rank.bullet_speed());
 
-            bullets.push(World::new_bullet(
-                enemy.coord + speed_direction * enemy.r,
-                speed,
-            ));
+            bullets.push(
+                World::new_bullet(enemy.coord + speed_direction * enemy.r, speed)
+                    .with_animation(self.animation),
+            );
             angle += d_angle;
         }
     }
+
+    fn data(&self) -> EmitterData {
+        EmitterData::Forward(self.clone())
+    }
+
+    fn scale(&mut self, difficulty: Difficulty) {
+        self.bullet_cooldown *= difficulty.cooldown();
+        self.bullet_number = difficulty.density(self.bullet_number);
+        self.bullet_speed *= difficulty.bullet_speed();
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct HardcodedEmitter {
     timer: f64,
     bullet_cooldown: f64,
@@ -218,6 +297,7 @@ impl BulletEmmiter for HardcodedEmitter {
         enemy: &Circle,
         _time: f64,
         delta: f64,
+        rank: Rank,
         bullets: &mut Vec<crate::world::Bullet>,
     ) {
         self.timer -= delta;
@@ -225,10 +305,33 @@ impl BulletEmmiter for HardcodedEmitter {
             return;
         }
 
-        self.timer += self.bullet_cooldown;
+        self.timer += self.bullet_cooldown * rank.cooldown();
 
         for (position, speed) in self.bullets.iter() {
-            bullets.push(World::new_bullet(enemy.coord + *position, *speed))
+            bullets.push(World::new_bullet(
+                enemy.coord + *position,
+                *speed * rank.bullet_speed(),
+            ))
+        }
+    }
+
+    fn origins(&self, enemy: &Circle) -> Vec<Vector> {
+        self.bullets
+            .iter()
+            .map(|(position, _)| enemy.coord + *position)
+            .collect()
+    }
+
+    fn data(&self) -> EmitterData {
+        EmitterData::Hardcoded(self.clone())
+    }
+
+    /// The shape is fixed, so harder difficulties fire it faster and more
+    /// often instead of with more bullets.
+    fn scale(&mut self, difficulty: Difficulty) {
+        self.bullet_cooldown *= difficulty.cooldown();
+        for (_, speed) in self.bullets.iter_mut() {
+            *speed = *speed * difficulty.bullet_speed();
         }
     }
 }
@@ -1,32 +1,52 @@
 use crate::{
+    animation::{AnimationSet, Animator, ClipName},
+    assets::TextureId,
+    difficulty::Difficulty,
+    events::WorldEvent,
     geometry::{Circle, Vector},
+    rank::Rank,
     world::Bullet,
 };
 
 use dyn_clone::DynClone;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use self::{bullet_emmiters::EmitterData, trajectories::TrajectoryData};
 
 pub mod bullet_emmiters;
 pub mod premade;
 pub mod trajectories;
 
-#[derive(Clone)]
+/// How fast practice takes an enemy back to where its attack starts.
+const TRAVEL_SPEED: f64 = 200.0;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Enemy {
-    pub sprite: String,
+    pub animation: Animator,
     pub display_width: f64,
     hitbox: Circle,
     pub health: f64,
+    max_health: f64,
     health_per_radius: f64,
     phases: Vec<Phase>,
     phase: usize,
     time: f64,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Phase {
     length: f64,
     trajectory: Box<dyn Trajectory>,
     bullets: Box<dyn BulletEmmiter>,
     next: Option<usize>,
+    /// The phase is left out below this difficulty.
+    min_difficulty: Difficulty,
+    /// Fire along with `bullets` from their difficulty up.
+    extra_bullets: Vec<(Difficulty, Box<dyn BulletEmmiter>)>,
+    /// Attacks are named and can be practiced on their own, the other
+    /// phases only take the enemy from one attack to the next.
+    #[serde(default)]
+    attack: Option<String>,
 }
 
 impl Phase {
@@ -40,6 +60,9 @@ impl Phase {
             trajectory,
             bullets,
             next: None,
+            min_difficulty: Difficulty::Easy,
+            extra_bullets: vec![],
+            attack: None,
         }
     }
 
@@ -54,7 +77,51 @@ impl Phase {
             trajectory,
             bullets,
             next: Some(jump_to),
+            min_difficulty: Difficulty::Easy,
+            extra_bullets: vec![],
+            attack: None,
+        }
+    }
+
+    pub fn with_min_difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.min_difficulty = difficulty;
+        self
+    }
+
+    pub fn with_bullets_from(
+        mut self,
+        difficulty: Difficulty,
+        bullets: Box<dyn BulletEmmiter>,
+    ) -> Self {
+        self.extra_bullets.push((difficulty, bullets));
+        self
+    }
+
+    pub fn attack(mut self, name: &str) -> Self {
+        self.attack = Some(name.to_string());
+        self
+    }
+
+    fn start(&self) -> Vector {
+        self.trajectory.location(0.0)
+    }
+
+    fn end(&self) -> Vector {
+        self.trajectory.location(self.length)
+    }
+
+    /// Goes from `from` to `to` without firing, `None` if it is already
+    /// there or never would be.
+    fn travel(from: Vector, to: Vector) -> Option<Self> {
+        let length = (to - from).len() / TRAVEL_SPEED;
+        if !(0.01..f64::INFINITY).contains(&length) {
+            return None;
         }
+        Some(Self::new(
+            length,
+            Box::new(trajectories::FromToTrajectory::new(from, to, length)),
+            Box::new(bullet_emmiters::HardcodedEmitter::new(length, vec![])),
+        ))
     }
 }
 
@@ -63,22 +130,74 @@ impl Enemy {
         hitbox: Circle,
         health: f64,
         phases: Vec<Phase>,
-        sprite: String,
+        sprite: TextureId,
         display_width: f64,
     ) -> Self {
         Self {
             health_per_radius: (hitbox.r * 0.7) / health,
             hitbox,
             health,
+            max_health: health,
             phases,
             time: 0.0,
             phase: 0,
-            sprite,
+            animation: Animator::new(AnimationSet::for_sprite(sprite)),
             display_width,
         }
     }
 
-    pub fn tick(&mut self, delta_time: f64, bullets: &mut Vec<Bullet>) {
+    pub fn with_animations(mut self, animations: AnimationSet) -> Self {
+        self.animation = Animator::new(animations);
+        self
+    }
+
+    /// Leaves out the phases and emitters meant for harder difficulties and
+    /// scales the remaining emitters. Jumps to a phase that was left out go
+    /// to the next one that wasn't.
+    pub fn for_difficulty(mut self, difficulty: Difficulty) -> Self {
+        let kept: Vec<bool> = self
+            .phases
+            .iter()
+            .map(|it| it.min_difficulty <= difficulty)
+            .collect();
+        if !kept.contains(&true) {
+            log::warn!("Every phase of an enemy is left out on {:?}", difficulty);
+            return self;
+        }
+        let new_index = |old: usize| {
+            let kept_at = (old..kept.len())
+                .chain(0..old)
+                .find(|it| kept[*it])
+                .unwrap_or(0);
+            kept[..kept_at].iter().filter(|it| **it).count()
+        };
+
+        let phases = std::mem::take(&mut self.phases);
+        for (phase, _) in phases.into_iter().zip(kept.iter()).filter(|(_, it)| **it) {
+            let mut bullets = phase.bullets;
+            for (from, extra) in phase.extra_bullets {
+                if from <= difficulty {
+                    bullets = Box::new(bullet_emmiters::CombinatorEmitter::new(bullets, extra));
+                }
+            }
+            bullets.scale(difficulty);
+            self.phases.push(Phase {
+                bullets,
+                next: phase.next.map(new_index),
+                extra_bullets: vec![],
+                ..phase
+            });
+        }
+        self
+    }
+
+    pub fn tick(
+        &mut self,
+        delta_time: f64,
+        rank: Rank,
+        bullets: &mut Vec<Bullet>,
+        events: &mut Vec<WorldEvent>,
+    ) {
         self.time += delta_time;
 
         let current_phase_length = self.phases[self.phase].length;
@@ -88,20 +207,109 @@ impl Enemy {
             self.phase = match self.phases[self.phase].next {
                 Some(next) => next,
                 None => (self.phase + 1) % self.phases.len(),
-            }
+            };
+            events.push(WorldEvent::PhaseChanged {
+                position: self.hitbox.coord,
+            });
         }
 
         let phase = &mut self.phases[self.phase];
 
+        let previous = self.hitbox.coord;
         self.hitbox.coord = phase.trajectory.location(self.time);
+        let speed = if delta_time > 0.0 {
+            (self.hitbox.coord.x - previous.x) / delta_time
+        } else {
+            0.0
+        };
+        self.animation.tick(delta_time, speed);
+        let bullets_before = bullets.len();
         phase
             .bullets
-            .tick(&self.hitbox, self.time, delta_time, bullets);
+            .tick(&self.hitbox, self.time, delta_time, rank, bullets);
+        if bullets.len() > bullets_before {
+            events.push(WorldEvent::EmitterFired {
+                position: self.hitbox.coord,
+            });
+        }
     }
 
     pub fn hit(&mut self, damage: f64) {
         self.hitbox.r -= damage * self.health_per_radius;
         self.health -= damage;
+        if self.is_alive() {
+            self.animation.trigger(ClipName::Hurt);
+        } else {
+            self.animation.trigger(ClipName::Death);
+        }
+    }
+
+    pub fn phase(&self) -> usize {
+        self.phase
+    }
+
+    /// The phases that can be practiced on their own, with their names.
+    pub fn attacks(&self) -> Vec<(usize, &str)> {
+        self.phases
+            .iter()
+            .enumerate()
+            .filter_map(|(i, it)| it.attack.as_deref().map(|name| (i, name)))
+            .collect()
+    }
+
+    /// Keeps only `phase`, one of `attacks`, to practice it. The enemy still
+    /// makes its entrance with the first phase, then moves to where the
+    /// attack starts and repeats it, going back to the start each time.
+    pub fn only_phase(mut self, phase: usize) -> Self {
+        let mut attack = self.phases.swap_remove(phase);
+        let mut phases = vec![];
+        if phase != 0 {
+            let entrance = self.phases.swap_remove(0);
+            phases.extend(Phase::travel(entrance.end(), attack.start()));
+            phases.insert(0, entrance);
+        }
+        let repeat = phases.len();
+        let back = Phase::travel(attack.end(), attack.start());
+        attack.next = if back.is_some() { None } else { Some(repeat) };
+        phases.push(attack);
+        phases.extend(back.map(|it| Phase {
+            next: Some(repeat),
+            ..it
+        }));
+        for it in phases[..repeat].iter_mut() {
+            it.next = None;
+        }
+
+        self.phases = phases;
+        self.phase = 0;
+        self.time = 0.0;
+        self
+    }
+
+    /// Time spent in the current phase and how long it lasts.
+    pub fn phase_time(&self) -> (f64, f64) {
+        (self.time, self.phases[self.phase].length)
+    }
+
+    pub fn emitter_origins(&self) -> Vec<Vector> {
+        self.phases[self.phase].bullets.origins(&self.hitbox)
+    }
+
+    /// Where the current phase's trajectory goes from now to its end.
+    pub fn path_ahead(&self, points: usize) -> Vec<Vector> {
+        let phase = &self.phases[self.phase];
+        let left = (phase.length - self.time).max(0.0);
+        (0..=points)
+            .map(|i| {
+                phase
+                    .trajectory
+                    .location(self.time + left * i as f64 / points as f64)
+            })
+            .collect()
+    }
+
+    pub fn health_fraction(&self) -> f64 {
+        (self.health / self.max_health).clamp(0.0, 1.0)
     }
 
     pub fn is_alive(&self) -> bool {
@@ -115,12 +323,194 @@ impl Enemy {
 
 pub trait Trajectory: DynClone {
     fn location(&self, time: f64) -> Vector;
+
+    /// The trajectory as plain data, which is how it is serialized.
+    fn data(&self) -> TrajectoryData;
 }
 
 dyn_clone::clone_trait_object!(Trajectory);
 
+/// Lets combinators hold trajectories restored from data.
+impl Trajectory for Box<dyn Trajectory> {
+    fn location(&self, time: f64) -> Vector {
+        (**self).location(time)
+    }
+
+    fn data(&self) -> TrajectoryData {
+        (**self).data()
+    }
+}
+
+impl Serialize for Box<dyn Trajectory> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.data().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn Trajectory> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        TrajectoryData::deserialize(deserializer).map(TrajectoryData::into_trajectory)
+    }
+}
+
 pub trait BulletEmmiter: DynClone {
-    fn tick(&mut self, enemy: &Circle, time: f64, delta: f64, bullets: &mut Vec<Bullet>);
+    /// `rank` nudges the pattern along with how well the player is doing,
+    /// the difficulty is already applied by `scale`.
+    fn tick(
+        &mut self,
+        enemy: &Circle,
+        time: f64,
+        delta: f64,
+        rank: Rank,
+        bullets: &mut Vec<Bullet>,
+    );
+
+    /// Where bullets leave from, for the debug overlay.
+    fn origins(&self, enemy: &Circle) -> Vec<Vector> {
+        vec![enemy.coord]
+    }
+
+    /// The emitter as plain data, timers included, which is how it is
+    /// serialized.
+    fn data(&self) -> EmitterData;
+
+    /// Adjusts speed, density and cooldown to `difficulty`, once when the
+    /// level is built.
+    fn scale(&mut self, difficulty: Difficulty);
 }
 
 dyn_clone::clone_trait_object!(BulletEmmiter);
+
+/// Lets combinators hold emitters restored from data.
+impl BulletEmmiter for Box<dyn BulletEmmiter> {
+    fn tick(
+        &mut self,
+        enemy: &Circle,
+        time: f64,
+        delta: f64,
+        rank: Rank,
+        bullets: &mut Vec<Bullet>,
+    ) {
+        (**self).tick(enemy, time, delta, rank, bullets)
+    }
+
+    fn origins(&self, enemy: &Circle) -> Vec<Vector> {
+        (**self).origins(enemy)
+    }
+
+    fn data(&self) -> EmitterData {
+        (**self).data()
+    }
+
+    fn scale(&mut self, difficulty: Difficulty) {
+        (**self).scale(difficulty)
+    }
+}
+
+impl Serialize for Box<dyn BulletEmmiter> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.data().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn BulletEmmiter> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        EmitterData::deserialize(deserializer).map(EmitterData::into_emitter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::Assets;
+    use bullet_emmiters::CircleEmitter;
+    use std::f64::consts::PI;
+    use trajectories::{CircleTrajectory, FromToTrajectory, StayTrajectory};
+
+    fn phase(jump_to: Option<usize>, min_difficulty: Difficulty) -> Phase {
+        let trajectory = Box::new(StayTrajectory::new(Vector::new(0.0, 0.0)));
+        let bullets = Box::new(CircleEmitter::new(1.0, 8, 100.0));
+        match jump_to {
+            Some(jump_to) => Phase::new_jump(1.0, trajectory, bullets, jump_to),
+            None => Phase::new(1.0, trajectory, bullets),
+        }
+        .with_min_difficulty(min_difficulty)
+    }
+
+    fn enemy(phases: Vec<Phase>) -> Enemy {
+        let sprite = Assets::load().texture("witch");
+        Enemy::new(Circle::new(0.0, 0.0, 10.0), 100.0, phases, sprite, 20.0)
+    }
+
+    fn jumps(enemy: &Enemy) -> Vec<Option<usize>> {
+        enemy.phases.iter().map(|it| it.next).collect()
+    }
+
+    #[test]
+    fn jump_to_a_dropped_phase_goes_to_the_next_kept_one() {
+        let enemy = enemy(vec![
+            phase(None, Difficulty::Easy),
+            phase(None, Difficulty::Hard),
+            phase(Some(1), Difficulty::Easy),
+        ]);
+        let easy = enemy.clone().for_difficulty(Difficulty::Easy);
+        assert_eq!(jumps(&easy), vec![None, Some(1)]);
+
+        let hard = enemy.for_difficulty(Difficulty::Hard);
+        assert_eq!(jumps(&hard), vec![None, None, Some(1)]);
+    }
+
+    #[test]
+    fn jump_to_a_dropped_last_phase_wraps_around() {
+        let enemy = enemy(vec![
+            phase(None, Difficulty::Easy),
+            phase(Some(2), Difficulty::Easy),
+            phase(None, Difficulty::Lunatic),
+        ]);
+        let normal = enemy.for_difficulty(Difficulty::Normal);
+        assert_eq!(jumps(&normal), vec![None, Some(0)]);
+    }
+
+    #[test]
+    fn practice_moves_between_the_entrance_and_the_repeated_attack() {
+        let bullets = || Box::new(CircleEmitter::new(1.0, 8, 100.0));
+        let enemy = enemy(vec![
+            Phase::new(
+                2.0,
+                Box::new(FromToTrajectory::new(
+                    Vector::new(0.0, -700.0),
+                    Vector::new(0.0, -250.0),
+                    2.0,
+                )),
+                bullets(),
+            ),
+            // Half a circle, from (50, -250) to (-250, -250).
+            Phase::new(
+                PI,
+                Box::new(CircleTrajectory::new(
+                    Circle::new(-100.0, -250.0, 150.0),
+                    PI / 2.0,
+                    1.0,
+                )),
+                bullets(),
+            )
+            .attack("Half circle"),
+            Phase::new_jump(
+                2.0,
+                Box::new(StayTrajectory::new(Vector::new(0.0, -250.0))),
+                bullets(),
+                1,
+            ),
+        ]);
+        assert_eq!(enemy.attacks(), vec![(1, "Half circle")]);
+
+        let practice = enemy.only_phase(1);
+        assert_eq!(jumps(&practice), vec![None, None, None, Some(2)]);
+        for (i, phase) in practice.phases.iter().enumerate() {
+            let next = phase.next.unwrap_or((i + 1) % practice.phases.len());
+            let gap = practice.phases[next].start() - phase.end();
+            assert!(gap.len() < 1e-6, "phase {} ends {} away", i, gap.len());
+        }
+        assert_eq!(practice.attacks(), vec![(2, "Half circle")]);
+    }
+}
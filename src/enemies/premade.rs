@@ -1,4 +1,7 @@
-use crate::geometry::{Circle, Vector};
+use crate::{
+    assets::TextureId,
+    geometry::{Circle, Vector},
+};
 
 use super::{
     bullet_emmiters::{CircleEmitter, ForwardEmitter},
@@ -6,7 +9,13 @@ use super::{
     Enemy, Phase,
 };
 
-pub fn enemy_1(start: Vector, offset: f64, rotation_speed: f64, points: Vec<Vector>) -> Enemy {
+pub fn enemy_1(
+    sprite: TextureId,
+    start: Vector,
+    offset: f64,
+    rotation_speed: f64,
+    points: Vec<Vector>,
+) -> Enemy {
     let mut stages = vec![
         Phase::new(
             5.0,
@@ -57,5 +66,5 @@ pub fn enemy_1(start: Vector, offset: f64, rotation_speed: f64, points: Vec<Vect
         1,
     ));
 
-    Enemy::new(Circle::new(0.0, 0.0, 30.0), 30.0, stages, "resources/ghost.png".to_string(), 100.0)
+    Enemy::new(Circle::new(0.0, 0.0, 30.0), 30.0, stages, sprite, 100.0)
 }
@@ -1,9 +1,32 @@
-use crate::{
-    geometry::{Circle, Vector},
-};
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::{Circle, Vector};
 
 use super::Trajectory;
 
+/// Every kind of trajectory, with what it is made of.
+#[derive(Serialize, Deserialize)]
+pub enum TrajectoryData {
+    Combinator(Box<TrajectoryData>, Box<TrajectoryData>),
+    Circle(CircleTrajectory),
+    Stay(StayTrajectory),
+    FromTo(FromToTrajectory),
+}
+
+impl TrajectoryData {
+    pub fn into_trajectory(self) -> Box<dyn Trajectory> {
+        match self {
+            TrajectoryData::Combinator(first, second) => Box::new(CombinatorTrajectory::new(
+                first.into_trajectory(),
+                second.into_trajectory(),
+            )),
+            TrajectoryData::Circle(it) => Box::new(it),
+            TrajectoryData::Stay(it) => Box::new(it),
+            TrajectoryData::FromTo(it) => Box::new(it),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct CombinatorTrajectory<First, Second>(First, Second);
 
@@ -19,9 +42,13 @@ impl<First: Trajectory + Clone, Second: Trajectory + Clone> Trajectory
     fn location(&self, time: f64) -> Vector {
         self.0.location(time) + self.1.location(time)
     }
+
+    fn data(&self) -> TrajectoryData {
+        TrajectoryData::Combinator(Box::new(self.0.data()), Box::new(self.1.data()))
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CircleTrajectory {
     pub trajectory: Circle,
     pub timer_offset: f64,
@@ -43,17 +70,20 @@ impl Trajectory for CircleTrajectory {
         let t = time * self.rotation_speed + self.timer_offset;
         self.trajectory.coord + Vector::new(t.sin(), t.cos()) * self.trajectory.r
     }
+
+    fn data(&self) -> TrajectoryData {
+        TrajectoryData::Circle(self.clone())
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct StayTrajectory {
     pub location: Vector,
 }
 
 impl StayTrajectory {
     pub fn new(location: Vector) -> Self {
-        Self {location
-        }
+        Self { location }
     }
 }
 
@@ -61,9 +91,13 @@ impl Trajectory for StayTrajectory {
     fn location(&self, _time: f64) -> Vector {
         self.location
     }
+
+    fn data(&self) -> TrajectoryData {
+        TrajectoryData::Stay(self.clone())
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct FromToTrajectory {
     pub from: Vector,
     pub speed: Vector,
@@ -82,4 +116,8 @@ impl Trajectory for FromToTrajectory {
     fn location(&self, time: f64) -> Vector {
         self.from + self.speed * time
     }
-}
\ No newline at end of file
+
+    fn data(&self) -> TrajectoryData {
+        TrajectoryData::FromTo(self.clone())
+    }
+}
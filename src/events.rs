@@ -0,0 +1,104 @@
+use crate::geometry::Vector;
+
+/// Things that happened inside the world during a tick. The world only
+/// records them; `App` drains the queue every frame and hands the events to
+/// whoever is interested (music, sound effects, ...).
+#[derive(Clone, Copy, Debug)]
+pub enum WorldEvent {
+    LevelStarted,
+    SceneStarted {
+        boss: bool,
+    },
+    LevelCleared,
+    PlayerDied,
+    PlayerShot {
+        position: Vector,
+    },
+    PlayerHit {
+        position: Vector,
+    },
+    BombUsed {
+        position: Vector,
+    },
+    Graze {
+        position: Vector,
+    },
+    EnemyHit {
+        position: Vector,
+    },
+    EnemyKilled {
+        position: Vector,
+    },
+    /// The last enemy of a boss scene went down, right after its
+    /// `EnemyKilled`.
+    BossKilled {
+        position: Vector,
+    },
+    BulletCancelled {
+        position: Vector,
+    },
+    PhaseChanged {
+        position: Vector,
+    },
+    EmitterFired {
+        position: Vector,
+    },
+}
+
+/// `WorldEvent` without its payload, used as a key when something has to be
+/// configured per type of event.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum EventKind {
+    LevelStarted,
+    SceneStarted,
+    LevelCleared,
+    PlayerDied,
+    PlayerShot,
+    PlayerHit,
+    BombUsed,
+    Graze,
+    EnemyHit,
+    EnemyKilled,
+    BossKilled,
+    BulletCancelled,
+    PhaseChanged,
+    EmitterFired,
+}
+
+impl WorldEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            WorldEvent::LevelStarted => EventKind::LevelStarted,
+            WorldEvent::SceneStarted { .. } => EventKind::SceneStarted,
+            WorldEvent::LevelCleared => EventKind::LevelCleared,
+            WorldEvent::PlayerDied => EventKind::PlayerDied,
+            WorldEvent::PlayerShot { .. } => EventKind::PlayerShot,
+            WorldEvent::PlayerHit { .. } => EventKind::PlayerHit,
+            WorldEvent::BombUsed { .. } => EventKind::BombUsed,
+            WorldEvent::Graze { .. } => EventKind::Graze,
+            WorldEvent::EnemyHit { .. } => EventKind::EnemyHit,
+            WorldEvent::EnemyKilled { .. } => EventKind::EnemyKilled,
+            WorldEvent::BossKilled { .. } => EventKind::BossKilled,
+            WorldEvent::BulletCancelled { .. } => EventKind::BulletCancelled,
+            WorldEvent::PhaseChanged { .. } => EventKind::PhaseChanged,
+            WorldEvent::EmitterFired { .. } => EventKind::EmitterFired,
+        }
+    }
+
+    /// Where in the world the event happened, if it happened somewhere.
+    pub fn position(&self) -> Option<Vector> {
+        match *self {
+            WorldEvent::PlayerShot { position }
+            | WorldEvent::PlayerHit { position }
+            | WorldEvent::BombUsed { position }
+            | WorldEvent::Graze { position }
+            | WorldEvent::EnemyHit { position }
+            | WorldEvent::EnemyKilled { position }
+            | WorldEvent::BossKilled { position }
+            | WorldEvent::BulletCancelled { position }
+            | WorldEvent::PhaseChanged { position }
+            | WorldEvent::EmitterFired { position } => Some(position),
+            _ => None,
+        }
+    }
+}
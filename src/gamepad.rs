@@ -0,0 +1,229 @@
+use std::collections::HashSet;
+
+use gloo::{
+    storage::{LocalStorage, Storage},
+    utils::window,
+};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::{Gamepad, GamepadButton};
+
+use crate::{geometry::Vector, input::Action};
+
+const CONFIG_KEY: &str = "tohou.gamepad";
+
+/// How far the stick has to lean, after the deadzone, to count as a
+/// direction press in menus.
+const STICK_PRESS: f64 = 0.5;
+
+/// Button indices follow the "standard" layout of the Gamepad API.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GamepadConfig {
+    pub deadzone: f64,
+    /// Snap the stick to eight directions, like a d-pad.
+    pub digital: bool,
+    pub buttons: Vec<(u32, Action)>,
+}
+
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.25,
+            digital: false,
+            buttons: vec![
+                (0, Action::Shoot),
+                (0, Action::Confirm),
+                (1, Action::Bomb),
+                (2, Action::SwitchWeapon),
+                (4, Action::Focus),
+                (5, Action::Focus),
+                (9, Action::Pause),
+                (12, Action::MoveUp),
+                (13, Action::MoveDown),
+                (14, Action::MoveLeft),
+                (15, Action::MoveRight),
+            ],
+        }
+    }
+}
+
+impl GamepadConfig {
+    pub fn load() -> Self {
+        LocalStorage::get(CONFIG_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = LocalStorage::set(CONFIG_KEY, self) {
+            log::error!("Failed to save gamepad config: {}", e);
+        }
+    }
+}
+
+/// State of the gamepad that was plugged in last, or of the latest one still
+/// connected once it's gone. It has to be polled every frame since the
+/// Gamepad API has no button events.
+pub struct GamepadInput {
+    pub config: GamepadConfig,
+    /// Indices of the connected pads, in the order they were plugged in.
+    connected: Vec<u32>,
+    held: HashSet<Action>,
+    /// Directions the stick leans in, so menus move once per lean.
+    stick_held: HashSet<Action>,
+    stick: Vector,
+}
+
+impl GamepadInput {
+    pub fn new(config: GamepadConfig) -> Self {
+        Self {
+            config,
+            connected: vec![],
+            held: HashSet::new(),
+            stick_held: HashSet::new(),
+            stick: Vector::zero(),
+        }
+    }
+
+    pub fn connect(&mut self, index: u32) {
+        log::info!("Gamepad {} connected", index);
+        let active = self.active();
+        self.connected.retain(|it| *it != index);
+        self.connected.push(index);
+        self.reset_if_changed(active);
+    }
+
+    pub fn disconnect(&mut self, index: u32) {
+        log::info!("Gamepad {} disconnected", index);
+        let active = self.active();
+        self.connected.retain(|it| *it != index);
+        self.reset_if_changed(active);
+    }
+
+    /// Index of the pad that is read.
+    pub fn active(&self) -> Option<u32> {
+        self.connected.last().copied()
+    }
+
+    /// Forgets what the previous pad held, so the new one starts fresh.
+    fn reset_if_changed(&mut self, previous: Option<u32>) {
+        if self.active() != previous {
+            self.held.clear();
+            self.stick_held.clear();
+            self.stick = Vector::zero();
+        }
+    }
+
+    pub fn is_held(&self, action: Action) -> bool {
+        self.held.contains(&action)
+    }
+
+    /// Stick position after the deadzone, each axis within [-1, 1].
+    pub fn stick(&self) -> Vector {
+        self.stick
+    }
+
+    /// Reads the current state of the gamepad and returns the actions whose
+    /// buttons went down since the last poll, with the directions the stick
+    /// started leaning in.
+    pub fn poll(&mut self) -> Vec<Action> {
+        let gamepad = match self.active().and_then(Self::gamepad) {
+            Some(gamepad) => gamepad,
+            None => return vec![],
+        };
+
+        let buttons = gamepad.buttons();
+        let mut held = HashSet::new();
+        for (index, action) in self.config.buttons.iter() {
+            let pressed = buttons
+                .get(*index)
+                .dyn_into::<GamepadButton>()
+                .map(|it| it.pressed())
+                .unwrap_or(false);
+            if pressed {
+                held.insert(*action);
+            }
+        }
+        let mut pressed: Vec<Action> = held.difference(&self.held).copied().collect();
+        self.held = held;
+
+        let axes = gamepad.axes();
+        let raw = Vector::new(
+            axes.get(0).as_f64().unwrap_or(0.0),
+            axes.get(1).as_f64().unwrap_or(0.0),
+        );
+        self.stick = self.apply_deadzone(raw);
+
+        let stick_held = Self::stick_directions(self.stick);
+        pressed.extend(stick_held.difference(&self.stick_held).copied());
+        self.stick_held = stick_held;
+
+        pressed
+    }
+
+    /// The d-pad actions a stick position leans towards. They only make
+    /// presses, holding the stick still moves through `stick()`.
+    fn stick_directions(stick: Vector) -> HashSet<Action> {
+        let mut directions = HashSet::new();
+        if stick.x <= -STICK_PRESS {
+            directions.insert(Action::MoveLeft);
+        }
+        if stick.x >= STICK_PRESS {
+            directions.insert(Action::MoveRight);
+        }
+        if stick.y <= -STICK_PRESS {
+            directions.insert(Action::MoveUp);
+        }
+        if stick.y >= STICK_PRESS {
+            directions.insert(Action::MoveDown);
+        }
+        directions
+    }
+
+    fn apply_deadzone(&self, raw: Vector) -> Vector {
+        let len = raw.len();
+        if len <= self.config.deadzone {
+            return Vector::zero();
+        }
+        let direction = raw.norm();
+        if self.config.digital {
+            let step = std::f64::consts::FRAC_PI_4;
+            let angle = (direction.y.atan2(direction.x) / step).round() * step;
+            return Vector::new(angle.cos(), angle.sin());
+        }
+        let len = ((len - self.config.deadzone) / (1.0 - self.config.deadzone)).min(1.0);
+        direction * len
+    }
+
+    fn gamepad(index: u32) -> Option<Gamepad> {
+        let gamepads = window().navigator().get_gamepads().ok()?;
+        gamepads.get(index).dyn_into::<Gamepad>().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn falls_back_to_a_pad_still_connected() {
+        let mut input = GamepadInput::new(GamepadConfig::default());
+        input.connect(0);
+        input.connect(1);
+        assert_eq!(input.active(), Some(1));
+        input.disconnect(0);
+        assert_eq!(input.active(), Some(1));
+        input.connect(2);
+        input.disconnect(2);
+        assert_eq!(input.active(), Some(1));
+        input.disconnect(1);
+        assert_eq!(input.active(), None);
+    }
+
+    #[test]
+    fn stick_leans_press_directions() {
+        let directions = GamepadInput::stick_directions(Vector::new(-0.8, 0.6));
+        assert!(directions.contains(&Action::MoveLeft));
+        assert!(directions.contains(&Action::MoveDown));
+        assert_eq!(directions.len(), 2);
+        assert!(GamepadInput::stick_directions(Vector::new(0.3, -0.2)).is_empty());
+    }
+}
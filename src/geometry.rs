@@ -1,6 +1,8 @@
 use std::ops::{Add, AddAssign, Mul, Sub};
 
-#[derive(Clone, Copy, Debug)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Vector {
     pub x: f64,
     pub y: f64,
@@ -64,7 +66,7 @@ impl Sub for Vector {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Circle {
     pub coord: Vector,
     pub r: f64,
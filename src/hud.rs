@@ -0,0 +1,123 @@
+use crate::{
+    difficulty::Difficulty,
+    geometry::Vector,
+    render::{Color, Frame, Layer, CANVAS_HEIGHT, CANVAS_WIDTH},
+    world::{BulletType, World},
+};
+
+/// The playfield takes the left of the canvas, the panel the rest.
+pub const PANEL_LEFT: f64 = 600.0;
+
+const PANEL_COLOR: Color = Color::rgb(30, 30, 60);
+
+/// How quickly the FPS counter follows changes, per frame.
+const FPS_SMOOTHING: f64 = 0.05;
+
+/// The side panel next to the playfield.
+pub struct Hud {
+    fps: f64,
+    last_frame: Option<f64>,
+}
+
+/// What the panel shows that the world doesn't know about.
+pub struct HudInfo {
+    pub level: usize,
+    pub levels: usize,
+    pub weapon: BulletType,
+    pub difficulty: Difficulty,
+    /// Whether a snapshot is kept, `None` when snapshots can't be used.
+    pub snapshot: Option<bool>,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Self {
+            fps: 0.0,
+            last_frame: None,
+        }
+    }
+
+    /// Called every animation frame with its timestamp in milliseconds,
+    /// paused or not, so the counter shows what the browser manages.
+    pub fn frame(&mut self, time: f64) {
+        if let Some(last) = self.last_frame {
+            let delta = (time - last) / 1000.0;
+            if delta > 0.0 {
+                let fps = 1.0 / delta;
+                if self.fps == 0.0 {
+                    self.fps = fps;
+                } else {
+                    self.fps += (fps - self.fps) * FPS_SMOOTHING;
+                }
+            }
+        }
+        self.last_frame = Some(time);
+    }
+
+    pub fn draw(&self, frame: &mut Frame, world: &World, info: &HudInfo) {
+        frame.rect(
+            Layer::Ui,
+            Vector::new(PANEL_LEFT, 0.0),
+            Vector::new(CANVAS_WIDTH - PANEL_LEFT, CANVAS_HEIGHT),
+            PANEL_COLOR,
+        );
+
+        let x = (PANEL_LEFT + CANVAS_WIDTH) / 2.0;
+        let mut top = 80.0;
+        frame.text(
+            Layer::Ui,
+            "Tohou",
+            Vector::new(x, top),
+            "48px bold",
+            Color::WHITE,
+        );
+        frame.text(
+            Layer::Ui,
+            info.difficulty.name(),
+            Vector::new(x, top + 45.0),
+            "24px bold",
+            Color::rgb(180, 180, 200),
+        );
+        top += 100.0;
+
+        let weapon = match info.weapon {
+            BulletType::PlayerHeavy => "Heavy",
+            _ => "Sniper",
+        };
+        let mut lines = vec![
+            format!("Level {}/{}", info.level + 1, info.levels),
+            format!(
+                "Scene {}/{}",
+                world.level.scene().max(1),
+                world.level.scenes.len()
+            ),
+            format!("Score: {}", world.score),
+            format!("Lives: {}", world.lives),
+            String::new(),
+            format!("Weapon: {}", weapon),
+            format!("Shots: x{}", world.player_bullets),
+            format!("Bombs: {}", world.bombs),
+        ];
+        if let Some(kept) = info.snapshot {
+            lines.push(format!("Snapshot: {}", if kept { "kept" } else { "none" }));
+        }
+        for line in lines {
+            frame.text(
+                Layer::Ui,
+                line,
+                Vector::new(x, top),
+                "28px bold",
+                Color::WHITE,
+            );
+            top += 50.0;
+        }
+
+        frame.text(
+            Layer::Ui,
+            format!("{:.0} FPS", self.fps),
+            Vector::new(x, CANVAS_HEIGHT - 30.0),
+            "20px bold",
+            Color::rgb(180, 180, 200),
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::{HashMap, HashSet};
+
+use gloo::storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gamepad::{GamepadConfig, GamepadInput},
+    geometry::Vector,
+    touch::TouchInput,
+};
+
+const BINDINGS_KEY: &str = "tohou.bindings";
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Shoot,
+    SwitchWeapon,
+    Focus,
+    Bomb,
+    Pause,
+    Confirm,
+    /// Keeps a snapshot of the world, to go back to with `RestoreSnapshot`.
+    SaveSnapshot,
+    RestoreSnapshot,
+}
+
+impl Action {
+    pub const ALL: [Action; 12] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::Shoot,
+        Action::SwitchWeapon,
+        Action::Focus,
+        Action::Bomb,
+        Action::Pause,
+        Action::Confirm,
+        Action::SaveSnapshot,
+        Action::RestoreSnapshot,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "Left",
+            Action::MoveRight => "Right",
+            Action::MoveUp => "Up",
+            Action::MoveDown => "Down",
+            Action::Shoot => "Shoot",
+            Action::SwitchWeapon => "Weapon",
+            Action::Focus => "Focus",
+            Action::Bomb => "Bomb",
+            Action::Pause => "Pause",
+            Action::Confirm => "Confirm",
+            Action::SaveSnapshot => "Snapshot",
+            Action::RestoreSnapshot => "Restore",
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::MoveLeft => &["ArrowLeft"],
+            Action::MoveRight => &["ArrowRight"],
+            Action::MoveUp => &["ArrowUp"],
+            Action::MoveDown => &["ArrowDown"],
+            Action::Shoot => &["Space"],
+            Action::SwitchWeapon => &["ControlLeft"],
+            Action::Focus => &["ShiftLeft"],
+            Action::Bomb => &["KeyX"],
+            Action::Pause => &["Escape"],
+            Action::Confirm => &["Enter"],
+            Action::SaveSnapshot => &["KeyO"],
+            Action::RestoreSnapshot => &["KeyP"],
+        }
+    }
+}
+
+/// Which `KeyboardEvent::code()`s trigger which action. Any number of keys
+/// can be bound to an action, and a key may end up bound to several actions;
+/// such conflicts are reported by `conflicts` but not forbidden.
+#[derive(Clone)]
+pub struct Bindings {
+    keys: HashMap<Action, Vec<String>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            keys: Action::ALL
+                .iter()
+                .map(|action| {
+                    let keys = action.default_keys().iter().map(|it| it.to_string());
+                    (*action, keys.collect())
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Bindings {
+    /// Reads the bindings saved in `localStorage`. Actions missing from the
+    /// saved data keep their default keys.
+    pub fn load() -> Self {
+        let mut bindings = Self::default();
+        match LocalStorage::get::<Vec<(Action, Vec<String>)>>(BINDINGS_KEY) {
+            Ok(saved) => bindings.keys.extend(saved),
+            Err(e) => log::debug!("Using default key bindings: {}", e),
+        }
+        bindings
+    }
+
+    pub fn save(&self) {
+        let saved = Action::ALL
+            .iter()
+            .map(|action| (*action, self.keys(*action).to_vec()))
+            .collect::<Vec<_>>();
+        if let Err(e) = LocalStorage::set(BINDINGS_KEY, saved) {
+            log::error!("Failed to save key bindings: {}", e);
+        }
+    }
+
+    pub fn keys(&self, action: Action) -> &[String] {
+        self.keys.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn actions(&self, key: &str) -> Vec<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .filter(|action| self.keys(*action).iter().any(|it| it == key))
+            .collect()
+    }
+
+    pub fn bind(&mut self, action: Action, key: &str) {
+        let keys = self.keys.entry(action).or_default();
+        if !keys.iter().any(|it| it == key) {
+            keys.push(key.to_string());
+        }
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.keys.insert(action, vec![]);
+    }
+
+    /// Other actions sharing a key with `action`.
+    pub fn conflicts(&self, action: Action) -> Vec<(String, Action)> {
+        let mut conflicts = vec![];
+        for key in self.keys(action) {
+            for other in self.actions(key) {
+                if other != action {
+                    conflicts.push((key.clone(), other));
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+/// Keyboard, gamepad and touch state translated into actions.
+pub struct Input {
+    pub bindings: Bindings,
+    pub gamepad: GamepadInput,
+    pub touch: TouchInput,
+    down: HashSet<String>,
+}
+
+impl Input {
+    pub fn new(bindings: Bindings, gamepad: GamepadConfig) -> Self {
+        Self {
+            bindings,
+            gamepad: GamepadInput::new(gamepad),
+            touch: TouchInput::new(),
+            down: HashSet::new(),
+        }
+    }
+
+    /// Registers a pressed key and returns the actions it triggers. Repeated
+    /// `keydown`s of a key that is already held trigger nothing.
+    pub fn key_down(&mut self, key: String) -> Vec<Action> {
+        let actions = self.bindings.actions(&key);
+        if self.down.insert(key) {
+            actions
+        } else {
+            vec![]
+        }
+    }
+
+    pub fn key_up(&mut self, key: &str) {
+        self.down.remove(key);
+    }
+
+    /// Forgets held keys and fingers. The page misses their `keyup`s and
+    /// `touchend`s once it loses focus, so they would stay held forever.
+    pub fn clear(&mut self) {
+        self.down.clear();
+        self.touch.clear();
+    }
+
+    /// Actions whose gamepad buttons went down since the previous frame.
+    pub fn poll_gamepad(&mut self) -> Vec<Action> {
+        self.gamepad.poll()
+    }
+
+    pub fn is_held(&self, action: Action) -> bool {
+        (action == Action::Shoot && self.touch.is_touching())
+            || self.gamepad.is_held(action)
+            || self
+                .bindings
+                .keys(action)
+                .iter()
+                .any(|it| self.down.contains(it))
+    }
+
+    /// Direction the player wants to move in, each axis within [-1, 1].
+    /// Digital input wins over the analog stick.
+    pub fn movement(&self) -> Vector {
+        let mut movement = Vector::zero();
+        if self.is_held(Action::MoveLeft) {
+            movement.x -= 1.0;
+        }
+        if self.is_held(Action::MoveRight) {
+            movement.x += 1.0;
+        }
+        if self.is_held(Action::MoveUp) {
+            movement.y -= 1.0;
+        }
+        if self.is_held(Action::MoveDown) {
+            movement.y += 1.0;
+        }
+        if movement.x == 0.0 && movement.y == 0.0 {
+            self.gamepad.stick()
+        } else {
+            movement
+        }
+    }
+}
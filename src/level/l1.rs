@@ -1,26 +1,53 @@
 use crate::{
+    animation::{AnimationFrame, AnimationSet, Clip, ClipName, LoopMode},
+    assets::{AssetList, Assets, TextureId},
+    background::{Background, BackgroundLayer, Tiling},
+    difficulty::Difficulty,
     enemies::{
-        bullet_emmiters::{CircleEmitter, ForwardEmitter, HardcodedEmitter, CombinatorEmitter},
+        bullet_emmiters::{CircleEmitter, CombinatorEmitter, ForwardEmitter, HardcodedEmitter},
         premade::enemy_1,
         trajectories::{CircleTrajectory, FromToTrajectory, StayTrajectory},
         Enemy, Phase,
     },
     geometry::{Circle, Vector},
+    music::LevelMusic,
+    render::Color,
+    world::BulletAnimation,
 };
 
 use super::{Level, Scene};
 
-pub fn l1() -> Level {
+pub fn l1(assets: &Assets) -> Level {
+    let ghost = assets.texture("ghost");
+    let witch = assets.texture("witch");
+    let floor = assets.texture("floor");
+    let forest = assets.texture("forest");
+    let stage = assets.track("resurrection");
+    let boss = assets.track("cypis");
+
     Level {
         scene: 0,
-        scenes: vec![l1s0(), l1s1(), l1s2(), l1s3()],
-        background: "resources/Floor.png".to_string(),
-        sound: "resources/resurrection.mp3".to_string(),
+        assets: AssetList::new(
+            vec![ghost, witch, floor, forest],
+            vec![stage.sound, boss.sound],
+        ),
+        scenes: vec![l1s0(ghost), l1s1(ghost), l1s2(ghost), l1s3(witch)],
+        background: Background::new(Color::rgb(40, 30, 30))
+            .with_layer(BackgroundLayer::new(floor, 50.0).with_tiling(Tiling::Vertical))
+            .with_layer(
+                BackgroundLayer::new(forest, 90.0)
+                    .with_tiling(Tiling::Vertical)
+                    .with_opacity(0.25),
+            ),
+        music: LevelMusic::new(stage).with_boss(boss),
+        sounds: vec![],
     }
 }
 
-fn l1s0() -> Scene {
+fn l1s0(ghost: TextureId) -> Scene {
     Scene {
+        boss: false,
+        scroll_speed: 1.0,
         enemies: vec![Enemy::new(
             Circle::new(0.0, 0.0, 20.0),
             30.0,
@@ -40,13 +67,13 @@ fn l1s0() -> Scene {
                     Box::new(ForwardEmitter::new(0.3, 2, Vector::new(0.0, 200.0), 0.5)),
                 ),
             ],
-            "resources/ghost.png".to_string(),
+            ghost,
             100.0,
         )],
     }
 }
 
-fn l1s1() -> Scene {
+fn l1s1(ghost: TextureId) -> Scene {
     let enemies = vec![
         (
             Vector::new(0.0, -600.0),
@@ -65,6 +92,8 @@ fn l1s1() -> Scene {
         ),
     ];
     Scene {
+        boss: false,
+        scroll_speed: 1.0,
         enemies: enemies
             .iter()
             .cloned()
@@ -106,7 +135,7 @@ fn l1s1() -> Scene {
                             1,
                         ),
                     ],
-                    "resources/ghost.png".to_string(),
+                    ghost,
                     100.0,
                 )
             })
@@ -114,10 +143,13 @@ fn l1s1() -> Scene {
     }
 }
 
-fn l1s2() -> Scene {
+fn l1s2(ghost: TextureId) -> Scene {
     Scene {
+        boss: false,
+        scroll_speed: 1.0,
         enemies: vec![
             enemy_1(
+                ghost,
                 Vector::new(-350.0, -550.0),
                 std::f64::consts::PI,
                 2.0,
@@ -128,6 +160,7 @@ fn l1s2() -> Scene {
                 ],
             ),
             enemy_1(
+                ghost,
                 Vector::new(350.0, -550.0),
                 0.0,
                 2.0,
@@ -141,8 +174,10 @@ fn l1s2() -> Scene {
     }
 }
 
-fn l1s3() -> Scene {
+fn l1s3(witch: TextureId) -> Scene {
     Scene {
+        boss: true,
+        scroll_speed: 0.3,
         enemies: vec![Enemy::new(
             Circle::new(0.0, 0.0, 20.0),
             30.0,
@@ -167,7 +202,8 @@ fn l1s3() -> Scene {
                         HardcodedEmitter::wall(0.5, 200.0, 300.0, 15),
                         CircleEmitter::new(0.3, 7, 200.0),
                     )),
-                ),
+                )
+                .attack("Walls and rings, left"),
                 Phase::new(
                     std::f64::consts::PI * 2.0,
                     Box::new(CircleTrajectory::new(
@@ -176,6 +212,14 @@ fn l1s3() -> Scene {
                         0.5,
                     )),
                     Box::new(HardcodedEmitter::hearth(0.5, 150.0, 30)),
+                )
+                .attack("Circling hearts")
+                .with_bullets_from(
+                    Difficulty::Hard,
+                    Box::new(
+                        CircleEmitter::new(0.6, 12, 150.0)
+                            .with_animation(BulletAnimation::Spin(6.0)),
+                    ),
                 ),
                 Phase::new(
                     std::f64::consts::PI * 1.0,
@@ -188,7 +232,8 @@ fn l1s3() -> Scene {
                         HardcodedEmitter::wall(0.5, 200.0, 300.0, 15),
                         CircleEmitter::new(0.3, 7, 200.0),
                     )),
-                ),
+                )
+                .attack("Walls and rings, right"),
                 Phase::new(
                     2.0,
                     Box::new(FromToTrajectory::new(
@@ -206,6 +251,11 @@ fn l1s3() -> Scene {
                         5.0,
                     )),
                     Box::new(HardcodedEmitter::hearth(0.5, 150.0, 30)),
+                )
+                .attack("Sweeping hearts")
+                .with_bullets_from(
+                    Difficulty::Lunatic,
+                    Box::new(ForwardEmitter::new(0.15, 3, Vector::new(0.0, 250.0), 0.4)),
                 ),
                 Phase::new_jump(
                     2.0,
@@ -218,8 +268,23 @@ fn l1s3() -> Scene {
                     1,
                 ),
             ],
-            "resources/witch.png".to_string(),
+            witch,
             200.0,
-        )],
+        )
+        .with_animations(witch_animations(witch))],
     }
 }
+
+/// The boss spins away instead of just fading.
+fn witch_animations(witch: TextureId) -> AnimationSet {
+    let death = (1..=12)
+        .map(|i| {
+            let t = i as f64 / 12.0;
+            AnimationFrame::new(witch, 0.06)
+                .with_angle(t * std::f64::consts::TAU * 2.0)
+                .with_scale(1.0 - t * 0.8)
+                .with_alpha(1.0 - t)
+        })
+        .collect();
+    AnimationSet::for_sprite(witch).with(ClipName::Death, Clip::new(LoopMode::Once, death))
+}
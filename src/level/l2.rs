@@ -1,27 +1,60 @@
 use crate::{
+    assets::{AssetList, Assets, TextureId},
+    background::{Background, BackgroundLayer},
+    difficulty::Difficulty,
     enemies::{
-        bullet_emmiters::{CircleEmitter, ForwardEmitter, HardcodedEmitter, CombinatorEmitter},
+        bullet_emmiters::{CircleEmitter, CombinatorEmitter, ForwardEmitter, HardcodedEmitter},
         trajectories::{CircleTrajectory, CombinatorTrajectory, FromToTrajectory, StayTrajectory},
         Enemy, Phase,
     },
+    events::EventKind,
     geometry::{Circle, Vector},
+    music::LevelMusic,
+    render::{BlendMode, Color},
+    sound_bank::SoundEffect,
+    world::BulletAnimation,
 };
 
 use super::{Level, Scene};
 
-pub fn l2() -> Level {
+pub fn l2(assets: &Assets) -> Level {
+    let ghost = assets.texture("ghost");
+    let floor = assets.texture("floor");
+    let forest = assets.texture("forest");
+    let stage = assets.track("cypis");
+    let boss = assets.track("resurrection");
+    let cancel = assets.sound("shoot_3");
+
     Level {
         scene: 0,
-        scenes: vec![l2s1(), l2s3()],
-        background: "resources/Floor.png".to_string(),
-        sound: "resources/cypis.mp3".to_string(),
+        assets: AssetList::new(
+            vec![ghost, floor, forest],
+            vec![stage.sound, boss.sound, cancel],
+        ),
+        scenes: vec![l2s1(ghost), l2s3(ghost)],
+        background: Background::new(Color::rgb(20, 40, 25))
+            .with_layer(BackgroundLayer::new(forest, 40.0))
+            .with_layer(
+                BackgroundLayer::new(floor, 70.0)
+                    .with_direction(Vector::new(0.3, 1.0))
+                    .with_opacity(0.3)
+                    .with_blend(BlendMode::Additive),
+            ),
+        music: LevelMusic::new(stage).with_boss(boss),
+        sounds: vec![(
+            EventKind::BulletCancelled,
+            Some(SoundEffect::new(cancel, 0.2, 0.05)),
+        )],
     }
 }
 
-fn l2s1() -> Scene {
+fn l2s1(ghost: TextureId) -> Scene {
     Scene {
+        boss: false,
+        scroll_speed: 1.0,
         enemies: vec![
             l2ls1_enemy(
+                ghost,
                 Vector::new(0.0, -550.0),
                 std::f64::consts::PI,
                 2.0,
@@ -32,6 +65,7 @@ fn l2s1() -> Scene {
                 ],
             ),
             l2ls1_enemy(
+                ghost,
                 Vector::new(0.0, -550.0),
                 0.0,
                 2.0,
@@ -45,7 +79,13 @@ fn l2s1() -> Scene {
     }
 }
 
-pub fn l2ls1_enemy(start: Vector, offset: f64, rotation_speed: f64, points: Vec<Vector>) -> Enemy {
+pub fn l2ls1_enemy(
+    sprite: TextureId,
+    start: Vector,
+    offset: f64,
+    rotation_speed: f64,
+    points: Vec<Vector>,
+) -> Enemy {
     let mut stages = vec![
         Phase::new(
             3.9,
@@ -66,11 +106,13 @@ pub fn l2ls1_enemy(start: Vector, offset: f64, rotation_speed: f64, points: Vec<
             )),
             Box::new(HardcodedEmitter::hearth(1.5, 100.0, 40)),
         ),
+        // Easy goes straight from the circle to the sweep.
         Phase::new(
             1.0,
             Box::new(StayTrajectory::new(points[0])),
             Box::new(CircleEmitter::new(0.2, 6, 200.0)),
-        ),
+        )
+        .with_min_difficulty(Difficulty::Normal),
     ];
     for i in 0..(points.len() - 1) {
         stages.push(Phase::new(
@@ -101,18 +143,14 @@ pub fn l2ls1_enemy(start: Vector, offset: f64, rotation_speed: f64, points: Vec<
         1,
     ));
 
-    Enemy::new(
-        Circle::new(0.0, 0.0, 30.0),
-        30.0,
-        stages,
-        "resources/ghost.png".to_string(),
-        100.0,
-    )
+    Enemy::new(Circle::new(0.0, 0.0, 30.0), 30.0, stages, sprite, 100.0)
 }
 
-fn l2s3() -> Scene {
+fn l2s3(ghost: TextureId) -> Scene {
     let start = Vector::new(0.0, -1000.0);
     Scene {
+        boss: true,
+        scroll_speed: 0.3,
         enemies: vec![Enemy::new(
             Circle::new(0.0, 0.0, 30.0),
             100.0,
@@ -142,12 +180,17 @@ fn l2s3() -> Scene {
                         ),
                     )),
                     Box::new(CombinatorEmitter::new(
-                        CircleEmitter::new(0.3, 7, 200.0),
-                        CircleEmitter::new(0.2, 7, 100.0),
+                        CircleEmitter::new(0.3, 7, 200.0)
+                            .with_animation(BulletAnimation::Spin(6.0)),
+                        CircleEmitter::new(0.2, 7, 100.0).with_animation(BulletAnimation::Pulse {
+                            rate: 3.0,
+                            amount: 0.2,
+                        }),
                     )),
-                ),
+                )
+                .attack("Spinning rings"),
             ],
-            "resources/ghost.png".to_string(),
+            ghost,
             100.0,
         )],
     }
@@ -5,27 +5,95 @@ pub use l1::l1;
 pub use l2::l2;
 
 use crate::{
+    assets::AssetList,
+    background::Background,
+    difficulty::Difficulty,
     enemies::Enemy,
+    events::WorldEvent,
+    music::LevelMusic,
+    sound_bank::SoundOverride,
     world::{Bullet, TickResult},
 };
 
 #[derive(Clone)]
 pub struct Level {
     scene: usize,
+    /// Everything the level draws or plays, besides what every level uses.
+    pub assets: AssetList,
     pub scenes: Vec<Scene>,
-    pub background: String,
-    pub sound: String,
+    pub background: Background,
+    pub music: LevelMusic,
+    pub sounds: Vec<SoundOverride>,
 }
 
 impl Level {
-    pub fn tick(&mut self, enemies: &mut Vec<Enemy>, bullets: &mut Vec<Bullet>) -> TickResult {
+    /// The level as it is played on `difficulty`, see
+    /// [`Enemy::for_difficulty`].
+    pub fn for_difficulty(mut self, difficulty: Difficulty) -> Self {
+        for scene in self.scenes.iter_mut() {
+            scene.enemies = std::mem::take(&mut scene.enemies)
+                .into_iter()
+                .map(|it| it.for_difficulty(difficulty))
+                .collect();
+        }
+        self
+    }
+
+    /// Only `scene`, for practice. With an `attack`, the scene's boss makes
+    /// its entrance and then repeats that attack until it goes down, see
+    /// [`Enemy::only_phase`].
+    pub fn practice(mut self, scene: usize, attack: Option<usize>) -> Self {
+        let mut scene = self.scenes.swap_remove(scene);
+        if let Some(attack) = attack {
+            if let Some(boss) = scene.enemies.pop() {
+                scene.enemies = vec![boss.only_phase(attack)];
+            }
+        }
+        self.scenes = vec![scene];
+        self.scene = 0;
+        self
+    }
+
+    /// How many scenes have started, which is the number of the current one.
+    pub fn scene(&self) -> usize {
+        self.scene
+    }
+
+    /// Goes back to a scene taken from `scene`, without spawning it.
+    pub fn restore_scene(&mut self, scene: usize) {
+        self.scene = scene.min(self.scenes.len());
+    }
+
+    /// How fast the background scrolls in the scene being played.
+    pub fn scroll_speed(&self) -> f64 {
+        match self.scene {
+            0 => 1.0,
+            scene => self.scenes[scene - 1].scroll_speed,
+        }
+    }
+
+    /// Whether the scene being played has a boss.
+    pub fn is_boss_scene(&self) -> bool {
+        self.scene > 0 && self.scenes[self.scene - 1].boss
+    }
+
+    pub fn tick(
+        &mut self,
+        enemies: &mut Vec<Enemy>,
+        bullets: &mut Vec<Bullet>,
+        events: &mut Vec<WorldEvent>,
+    ) -> TickResult {
         if enemies.is_empty() && self.scene == self.scenes.len() {
+            events.push(WorldEvent::LevelCleared);
             return TickResult::Win;
         }
         if enemies.is_empty() {
             log::debug!("Level 1, Scene {}", self.scene);
             bullets.drain(..);
             self.scenes[self.scene].spawn(enemies);
+            events.push(WorldEvent::SceneStarted {
+                boss: self.scenes[self.scene].boss,
+            });
             self.scene += 1;
         }
         TickResult::None
@@ -35,9 +103,20 @@ impl Level {
 #[derive(Clone)]
 pub struct Scene {
     enemies: Vec<Enemy>,
+    boss: bool,
+    /// Multiplies the speed of every background layer.
+    scroll_speed: f64,
 }
 
 impl Scene {
+    pub fn enemies(&self) -> &[Enemy] {
+        &self.enemies
+    }
+
+    pub fn is_boss(&self) -> bool {
+        self.boss
+    }
+
     fn spawn(&self, to: &mut Vec<Enemy>) {
         for enemy in self.enemies.iter() {
             to.push(enemy.clone());
@@ -1,11 +1,34 @@
+mod animation;
 mod app;
+mod assets;
+mod atlas;
 mod audio;
+mod background;
+mod controls;
+mod debug;
+mod difficulty;
+mod download;
 mod enemies;
+mod events;
+mod gamepad;
 mod geometry;
+mod hud;
+mod input;
 mod level;
+mod music;
+mod particles;
+mod practice;
+mod rank;
+mod records;
+mod records_screen;
+mod render;
+mod save;
+mod screens;
+mod settings;
+mod sound_bank;
 mod textures;
+mod touch;
 mod world;
-mod download;
 
 fn main() {
     wasm_logger::init(wasm_logger::Config::default());
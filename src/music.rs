@@ -0,0 +1,243 @@
+use web_sys::HtmlAudioElement;
+
+use crate::{assets::SoundId, audio::AudioManager, events::WorldEvent};
+
+const BOSS_CROSSFADE: f64 = 2.0;
+const START_FADE: f64 = 0.5;
+const GAME_OVER_FADE: f64 = 1.5;
+const LEVEL_CLEAR_FADE: f64 = 3.0;
+
+/// A piece of music. When `loop_start` is not zero everything before it is
+/// an intro that is played once; the part between `loop_start` and
+/// `loop_end` (or the end of the file) is repeated forever. Tracks are
+/// usually made by `Assets::track`, which fills these in from the manifest.
+#[derive(Clone, PartialEq)]
+pub struct Track {
+    pub sound: SoundId,
+    pub volume: f64,
+    pub loop_start: f64,
+    pub loop_end: Option<f64>,
+}
+
+impl Track {
+    pub fn new(sound: SoundId) -> Self {
+        Self {
+            sound,
+            volume: 0.5,
+            loop_start: 0.0,
+            loop_end: None,
+        }
+    }
+
+    /// Without a loop end the element repeats the file by itself, which
+    /// leaves no gap between the end and the start.
+    fn native_loop(&self) -> bool {
+        self.loop_end.is_none()
+    }
+}
+
+#[derive(Clone)]
+pub struct LevelMusic {
+    pub stage: Track,
+    pub boss: Option<Track>,
+}
+
+impl LevelMusic {
+    pub fn new(stage: Track) -> Self {
+        Self { stage, boss: None }
+    }
+
+    pub fn with_boss(self, boss: Track) -> Self {
+        Self {
+            boss: Some(boss),
+            ..self
+        }
+    }
+}
+
+struct Channel {
+    audio: HtmlAudioElement,
+    track: Track,
+    gain: f64,
+    target: f64,
+    fade_speed: f64,
+    /// Whether playback has reached `loop_start` since the last check.
+    past_intro: bool,
+}
+
+impl Channel {
+    fn apply_volume(&self, master: f64) {
+        self.audio
+            .set_volume((self.track.volume * self.gain * master).clamp(0.0, 1.0));
+    }
+
+    fn update_loop(&mut self) {
+        let time = self.audio.current_time();
+        match self.track.loop_end {
+            Some(end) if time >= end => {
+                let length = end - self.track.loop_start;
+                self.audio.set_current_time(time - length);
+            }
+            // A native loop goes back to the very start, the intro is
+            // skipped again as soon as it shows up.
+            None if self.past_intro && time < self.track.loop_start => {
+                self.audio.set_current_time(self.track.loop_start);
+            }
+            _ => (),
+        }
+        self.past_intro = time >= self.track.loop_start;
+    }
+}
+
+/// Owns whatever music is currently audible. Fades and loop points are
+/// driven by `tick`, so it has to be ticked every frame while anything is
+/// playing, including the frames after the simulation has stopped.
+pub struct MusicController {
+    channels: Vec<Channel>,
+    paused: bool,
+    volume: f64,
+}
+
+impl MusicController {
+    pub fn new() -> Self {
+        Self {
+            channels: vec![],
+            paused: false,
+            volume: 1.0,
+        }
+    }
+
+    pub fn set_volume(&mut self, volume: f64) {
+        self.volume = volume;
+        for channel in self.channels.iter() {
+            channel.apply_volume(volume);
+        }
+    }
+
+    pub fn handle(&mut self, event: &WorldEvent, music: &LevelMusic, audio: &AudioManager) {
+        match event {
+            WorldEvent::LevelStarted => self.play(&music.stage, audio, START_FADE),
+            WorldEvent::SceneStarted { boss: true, .. } => {
+                if let Some(boss) = &music.boss {
+                    self.play(boss, audio, BOSS_CROSSFADE);
+                }
+            }
+            WorldEvent::LevelCleared => self.fade_out(LEVEL_CLEAR_FADE),
+            WorldEvent::PlayerDied => self.fade_out(GAME_OVER_FADE),
+            _ => (),
+        }
+    }
+
+    /// Starts `track` from the beginning, crossfading everything else out
+    /// over `fade` seconds. Does nothing if the track is already the one
+    /// fading in or playing.
+    pub fn play(&mut self, track: &Track, audio: &AudioManager, fade: f64) {
+        if self.is_playing(track) {
+            return;
+        }
+        self.channels.retain(|it| {
+            if it.track.sound == track.sound {
+                AudioManager::stop(&it.audio);
+                false
+            } else {
+                true
+            }
+        });
+        self.fade_out(fade);
+
+        let element = match audio.get(track.sound) {
+            Some(element) => element,
+            None => return,
+        };
+        AudioManager::stop(&element);
+        let channel = Channel {
+            audio: element,
+            track: track.clone(),
+            gain: if fade > 0.0 { 0.0 } else { 1.0 },
+            target: 1.0,
+            fade_speed: Self::fade_speed(fade),
+            past_intro: false,
+        };
+        channel.apply_volume(self.volume);
+        if !self.paused {
+            AudioManager::play(
+                channel.audio.clone(),
+                track.native_loop(),
+                false,
+                channel.audio.volume(),
+            );
+        }
+        self.channels.push(channel);
+    }
+
+    pub fn fade_out(&mut self, fade: f64) {
+        for channel in self.channels.iter_mut() {
+            channel.target = 0.0;
+            channel.fade_speed = Self::fade_speed(fade);
+        }
+    }
+
+    pub fn pause(&mut self) {
+        if self.paused {
+            return;
+        }
+        self.paused = true;
+        for channel in self.channels.iter() {
+            if let Err(e) = channel.audio.pause() {
+                log::warn!("Failed to pause music: {:?}", e);
+            }
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if !self.paused {
+            return;
+        }
+        self.paused = false;
+        for channel in self.channels.iter() {
+            AudioManager::play(
+                channel.audio.clone(),
+                channel.track.native_loop(),
+                false,
+                channel.audio.volume(),
+            );
+        }
+    }
+
+    pub fn tick(&mut self, delta: f64) {
+        if self.paused {
+            return;
+        }
+        for channel in self.channels.iter_mut() {
+            if channel.gain < channel.target {
+                channel.gain = (channel.gain + channel.fade_speed * delta).min(channel.target);
+            } else if channel.gain > channel.target {
+                channel.gain = (channel.gain - channel.fade_speed * delta).max(channel.target);
+            }
+            channel.apply_volume(self.volume);
+            channel.update_loop();
+        }
+        self.channels.retain(|it| {
+            if it.target == 0.0 && it.gain == 0.0 {
+                AudioManager::stop(&it.audio);
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    fn is_playing(&self, track: &Track) -> bool {
+        self.channels
+            .iter()
+            .any(|it| it.track == *track && it.target > 0.0)
+    }
+
+    fn fade_speed(fade: f64) -> f64 {
+        if fade > 0.0 {
+            1.0 / fade
+        } else {
+            f64::MAX
+        }
+    }
+}
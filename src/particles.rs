@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+use crate::{
+    events::{EventKind, WorldEvent},
+    geometry::Vector,
+    render::{BlendMode, Color, Frame, Layer},
+};
+
+/// Particles beyond this are not spawned, so a bomb over a screen full of
+/// bullets can't stall a frame.
+const MAX_PARTICLES: usize = 1500;
+
+/// What an effect spawns. Ranges are `(min, max)` picked at random per
+/// particle, the `(start, end)` pairs are blended over a particle's life.
+#[derive(Clone)]
+pub struct ParticleEmitter {
+    /// Spawned at once when the effect starts.
+    pub burst: usize,
+    /// Spawned per second for `duration` after the burst.
+    pub rate: f64,
+    pub duration: f64,
+    pub lifetime: (f64, f64),
+    pub speed: (f64, f64),
+    /// Middle of the cone particles fly out in, in radians. Zero is right.
+    pub direction: f64,
+    /// Full width of the cone, `TAU` for every direction.
+    pub spread: f64,
+    pub gravity: Vector,
+    pub color: (Color, Color),
+    pub size: (f64, f64),
+    pub alpha: (f64, f64),
+    pub blend: BlendMode,
+}
+
+impl ParticleEmitter {
+    fn hit_sparks() -> Self {
+        Self {
+            burst: 6,
+            rate: 0.0,
+            duration: 0.0,
+            lifetime: (0.1, 0.25),
+            speed: (150.0, 350.0),
+            direction: -std::f64::consts::FRAC_PI_2,
+            spread: 2.0,
+            gravity: Vector::new(0.0, 400.0),
+            color: (Color::rgb(255, 255, 200), Color::rgb(255, 160, 0)),
+            size: (3.0, 1.0),
+            alpha: (1.0, 0.0),
+            blend: BlendMode::Additive,
+        }
+    }
+
+    fn explosion() -> Self {
+        Self {
+            burst: 30,
+            rate: 150.0,
+            duration: 0.15,
+            lifetime: (0.3, 0.7),
+            speed: (50.0, 250.0),
+            direction: 0.0,
+            spread: std::f64::consts::TAU,
+            gravity: Vector::new(0.0, 60.0),
+            color: (Color::rgb(255, 220, 120), Color::rgb(200, 40, 0)),
+            size: (7.0, 2.0),
+            alpha: (1.0, 0.0),
+            blend: BlendMode::Additive,
+        }
+    }
+
+    fn puff() -> Self {
+        Self {
+            burst: 4,
+            rate: 0.0,
+            duration: 0.0,
+            lifetime: (0.2, 0.4),
+            speed: (10.0, 40.0),
+            direction: 0.0,
+            spread: std::f64::consts::TAU,
+            gravity: Vector::new(0.0, -30.0),
+            color: (Color::WHITE, Color::rgb(150, 150, 170)),
+            size: (3.0, 6.0),
+            alpha: (0.6, 0.0),
+            blend: BlendMode::Normal,
+        }
+    }
+
+    fn graze_sparks() -> Self {
+        Self {
+            burst: 3,
+            rate: 0.0,
+            duration: 0.0,
+            lifetime: (0.1, 0.2),
+            speed: (80.0, 160.0),
+            direction: 0.0,
+            spread: std::f64::consts::TAU,
+            gravity: Vector::zero(),
+            color: (Color::CYAN, Color::rgb(0, 80, 255)),
+            size: (2.0, 1.0),
+            alpha: (1.0, 0.2),
+            blend: BlendMode::Additive,
+        }
+    }
+
+    fn player_explosion() -> Self {
+        Self {
+            burst: 80,
+            rate: 200.0,
+            duration: 0.3,
+            lifetime: (0.5, 1.2),
+            speed: (40.0, 320.0),
+            direction: 0.0,
+            spread: std::f64::consts::TAU,
+            gravity: Vector::zero(),
+            color: (Color::WHITE, Color::rgb(255, 60, 200)),
+            size: (8.0, 2.0),
+            alpha: (1.0, 0.0),
+            blend: BlendMode::Additive,
+        }
+    }
+
+    fn spawn(&self, position: Vector) -> Particle {
+        let angle = self.direction + (rand::random::<f64>() - 0.5) * self.spread;
+        let speed = pick(self.speed);
+        Particle {
+            position,
+            velocity: Vector::new(angle.cos(), angle.sin()) * speed,
+            age: 0.0,
+            lifetime: pick(self.lifetime),
+            color: self.color,
+            size: self.size,
+            alpha: self.alpha,
+            gravity: self.gravity,
+            blend: self.blend,
+        }
+    }
+}
+
+fn pick((min, max): (f64, f64)) -> f64 {
+    min + rand::random::<f64>() * (max - min)
+}
+
+fn blend((start, end): (f64, f64), t: f64) -> f64 {
+    start + (end - start) * t
+}
+
+struct Particle {
+    position: Vector,
+    velocity: Vector,
+    age: f64,
+    lifetime: f64,
+    color: (Color, Color),
+    size: (f64, f64),
+    alpha: (f64, f64),
+    gravity: Vector,
+    blend: BlendMode,
+}
+
+impl Particle {
+    fn color(&self) -> Color {
+        let t = self.age / self.lifetime;
+        let (start, end) = self.color;
+        let channel = |a: u8, b: u8| blend((a as f64, b as f64), t).round() as u8;
+        Color::rgba(
+            channel(start.r, end.r),
+            channel(start.g, end.g),
+            channel(start.b, end.b),
+            blend(self.alpha, t) * blend((start.a, end.a), t),
+        )
+    }
+}
+
+/// An emitter that keeps spawning for a while after its burst.
+struct Running {
+    emitter: ParticleEmitter,
+    position: Vector,
+    time: f64,
+    spawned: f64,
+}
+
+/// Turns gameplay events into particle effects. Positions are in world
+/// coordinates, like the events they come from.
+pub struct ParticleSystem {
+    effects: HashMap<EventKind, ParticleEmitter>,
+    running: Vec<Running>,
+    particles: Vec<Particle>,
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        Self {
+            effects: HashMap::from([
+                (EventKind::EnemyHit, ParticleEmitter::hit_sparks()),
+                (EventKind::EnemyKilled, ParticleEmitter::explosion()),
+                (EventKind::BulletCancelled, ParticleEmitter::puff()),
+                (EventKind::Graze, ParticleEmitter::graze_sparks()),
+                (EventKind::PlayerHit, ParticleEmitter::player_explosion()),
+            ]),
+            running: vec![],
+            particles: vec![],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.running.clear();
+        self.particles.clear();
+    }
+
+    pub fn handle(&mut self, events: &[WorldEvent]) {
+        for event in events {
+            let position = match event.position() {
+                Some(position) => position,
+                None => continue,
+            };
+            if let Some(emitter) = self.effects.get(&event.kind()).cloned() {
+                self.start(emitter, position);
+            }
+        }
+    }
+
+    fn start(&mut self, emitter: ParticleEmitter, position: Vector) {
+        for _ in 0..emitter.burst {
+            self.spawn(&emitter, position);
+        }
+        if emitter.rate > 0.0 && emitter.duration > 0.0 {
+            self.running.push(Running {
+                emitter,
+                position,
+                time: 0.0,
+                spawned: 0.0,
+            });
+        }
+    }
+
+    pub fn tick(&mut self, delta: f64) {
+        let mut running = std::mem::take(&mut self.running);
+        for it in running.iter_mut() {
+            it.time = (it.time + delta).min(it.emitter.duration);
+            let due = it.time * it.emitter.rate;
+            while it.spawned + 1.0 <= due {
+                it.spawned += 1.0;
+                self.spawn(&it.emitter, it.position);
+            }
+        }
+        running.retain(|it| it.time < it.emitter.duration);
+        self.running = running;
+
+        for particle in self.particles.iter_mut() {
+            particle.velocity += particle.gravity * delta;
+            particle.position += particle.velocity * delta;
+            particle.age += delta;
+        }
+        self.particles.retain(|it| it.age < it.lifetime);
+    }
+
+    /// `origin` is where the world's origin is on the canvas.
+    pub fn draw(&self, frame: &mut Frame, origin: Vector) {
+        for particle in self.particles.iter() {
+            let t = particle.age / particle.lifetime;
+            frame.particle(
+                Layer::World,
+                origin + particle.position,
+                blend(particle.size, t),
+                particle.color(),
+                particle.blend,
+            );
+        }
+    }
+
+    fn spawn(&mut self, emitter: &ParticleEmitter, position: Vector) {
+        if self.particles.len() < MAX_PARTICLES {
+            self.particles.push(emitter.spawn(position));
+        }
+    }
+}
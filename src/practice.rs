@@ -0,0 +1,356 @@
+use gloo::storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    difficulty::Difficulty,
+    geometry::Vector,
+    input::Action,
+    level::Level,
+    render::{Color, Frame, Layer, CANVAS_WIDTH},
+    save::{stored, version},
+    screens::fill,
+    world::START_LIVES,
+};
+
+const STATS_KEY: &str = "tohou.practice";
+/// Bumped whenever the format changes, along with a step in `migrate`.
+const STATS_VERSION: u64 = 1;
+
+const MAX_POWER: usize = 5;
+const MAX_LIVES: usize = 9;
+
+/// Rows of the list shown at once, the rest scroll into view.
+const ROWS_SHOWN: usize = 12;
+
+const BACKGROUND: Color = Color::rgb(60, 60, 120);
+
+/// Where a practice starts: a whole scene, or only one attack of the
+/// scene's boss, which then repeats until the boss goes down. Practicing
+/// on another difficulty is another target.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PracticeTarget {
+    /// Stats kept before there was one are for `Normal`.
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    pub level: usize,
+    pub scene: usize,
+    /// A phase of the boss.
+    pub attack: Option<usize>,
+}
+
+impl PracticeTarget {
+    /// The level to play, holding only what is practiced. `levels` are as
+    /// played on the target's difficulty.
+    pub fn level(self, levels: &[Level]) -> Level {
+        levels[self.level].clone().practice(self.scene, self.attack)
+    }
+}
+
+/// What the player starts a practice with.
+#[derive(Clone, Copy)]
+pub struct PracticeOptions {
+    /// `World::player_bullets`.
+    pub power: usize,
+    pub lives: usize,
+    /// Hits don't cost a life, the practice only ends when it is cleared.
+    pub infinite_lives: bool,
+}
+
+impl Default for PracticeOptions {
+    fn default() -> Self {
+        Self {
+            power: 1,
+            lives: START_LIVES,
+            infinite_lives: false,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct TargetStats {
+    target: PracticeTarget,
+    attempts: u32,
+    clears: u32,
+}
+
+/// How often each scene and attack was practiced and cleared.
+#[derive(Serialize, Deserialize)]
+pub struct PracticeStats {
+    version: u64,
+    targets: Vec<TargetStats>,
+    /// The stored stats couldn't be read, or are from a newer version. They
+    /// are played without but never saved over.
+    #[serde(skip)]
+    read_only: bool,
+}
+
+impl Default for PracticeStats {
+    fn default() -> Self {
+        Self {
+            version: STATS_VERSION,
+            targets: vec![],
+            read_only: false,
+        }
+    }
+}
+
+impl PracticeStats {
+    pub fn load() -> Self {
+        let value = match stored(STATS_KEY) {
+            Ok(Value::Null) => return Self::default(),
+            Ok(value) => value,
+            Err(e) => {
+                log::error!("Failed to read practice stats, playing without them: {}", e);
+                return Self::read_only();
+            }
+        };
+        let newer = version(&value) > STATS_VERSION;
+        match serde_json::from_value::<Self>(migrate(value)) {
+            Ok(stats) => Self {
+                read_only: newer,
+                ..stats
+            },
+            Err(e) => {
+                log::error!("Failed to read practice stats, playing without them: {}", e);
+                Self::read_only()
+            }
+        }
+    }
+
+    fn read_only() -> Self {
+        Self {
+            read_only: true,
+            ..Self::default()
+        }
+    }
+
+    fn save(&self) {
+        if self.read_only {
+            log::warn!("Not saving over practice stats that can't be read");
+            return;
+        }
+        if let Err(e) = LocalStorage::set(STATS_KEY, self) {
+            log::error!("Failed to save practice stats: {}", e);
+        }
+    }
+
+    /// Attempts and clears.
+    fn get(&self, target: PracticeTarget) -> (u32, u32) {
+        self.targets
+            .iter()
+            .find(|it| it.target == target)
+            .map_or((0, 0), |it| (it.attempts, it.clears))
+    }
+
+    fn entry(&mut self, target: PracticeTarget) -> &mut TargetStats {
+        let index = match self.targets.iter().position(|it| it.target == target) {
+            Some(index) => index,
+            None => {
+                self.targets.push(TargetStats {
+                    target,
+                    attempts: 0,
+                    clears: 0,
+                });
+                self.targets.len() - 1
+            }
+        };
+        &mut self.targets[index]
+    }
+
+    pub fn attempted(&mut self, target: PracticeTarget) {
+        self.entry(target).attempts += 1;
+        self.save();
+    }
+
+    pub fn cleared(&mut self, target: PracticeTarget) {
+        self.entry(target).clears += 1;
+        self.save();
+    }
+}
+
+/// Upgrades the stats one version at a time, anything that isn't an object
+/// is left for reading them to fail.
+fn migrate(mut stats: Value) -> Value {
+    if version(&stats) > STATS_VERSION {
+        return stats;
+    }
+    // Version 0 only lacked the version.
+    if let Some(fields) = stats.as_object_mut() {
+        fields.insert("version".to_string(), json!(STATS_VERSION));
+    }
+    stats
+}
+
+enum Row {
+    Power,
+    Lives,
+    InfiniteLives,
+    /// With what the list calls it.
+    Target(PracticeTarget, String),
+}
+
+pub enum PracticeResult {
+    Stay,
+    Close,
+    Start(PracticeTarget),
+}
+
+/// Lists every scene of every level and each attack of the boss scenes,
+/// under the options the practice starts with.
+pub struct PracticeScreen {
+    difficulty: Difficulty,
+    rows: Vec<Row>,
+    selected: usize,
+    pub options: PracticeOptions,
+}
+
+impl PracticeScreen {
+    /// `levels` as played on `difficulty`, which decides the boss attacks
+    /// there are. `selected` is the target practiced last.
+    pub fn new(
+        levels: &[Level],
+        difficulty: Difficulty,
+        options: PracticeOptions,
+        selected: Option<PracticeTarget>,
+    ) -> Self {
+        let mut rows = vec![Row::Power, Row::Lives, Row::InfiniteLives];
+        for (l, level) in levels.iter().enumerate() {
+            for (s, scene) in level.scenes.iter().enumerate() {
+                let target = PracticeTarget {
+                    difficulty,
+                    level: l,
+                    scene: s,
+                    attack: None,
+                };
+                rows.push(Row::Target(
+                    target,
+                    format!("Level {}, scene {}", l + 1, s + 1),
+                ));
+                if !scene.is_boss() {
+                    continue;
+                }
+                let attacks = scene.enemies().last().map_or(vec![], |it| it.attacks());
+                for (attack, name) in attacks {
+                    let target = PracticeTarget {
+                        attack: Some(attack),
+                        ..target
+                    };
+                    rows.push(Row::Target(target, format!("- {}", name)));
+                }
+            }
+        }
+        let selected = rows
+            .iter()
+            .position(|it| matches!(it, Row::Target(target, _) if Some(*target) == selected))
+            .unwrap_or(0);
+        Self {
+            difficulty,
+            rows,
+            selected,
+            options,
+        }
+    }
+
+    pub fn action(&mut self, action: Action) -> PracticeResult {
+        let len = self.rows.len();
+        let options = &mut self.options;
+        match (action, &self.rows[self.selected]) {
+            (Action::MoveUp, _) => self.selected = (self.selected + len - 1) % len,
+            (Action::MoveDown, _) => self.selected = (self.selected + 1) % len,
+            (Action::MoveLeft, Row::Power) => options.power = (options.power - 1).max(1),
+            (Action::MoveRight, Row::Power) => options.power = (options.power + 1).min(MAX_POWER),
+            (Action::MoveLeft, Row::Lives) => options.lives = (options.lives - 1).max(1),
+            (Action::MoveRight, Row::Lives) => options.lives = (options.lives + 1).min(MAX_LIVES),
+            (Action::MoveLeft | Action::MoveRight | Action::Confirm, Row::InfiniteLives) => {
+                options.infinite_lives = !options.infinite_lives
+            }
+            (Action::Confirm, Row::Target(target, _)) => return PracticeResult::Start(*target),
+            (Action::Pause, _) => return PracticeResult::Close,
+            _ => (),
+        }
+        PracticeResult::Stay
+    }
+
+    pub fn draw(&self, frame: &mut Frame, stats: &PracticeStats) {
+        fill(frame, BACKGROUND);
+        let x = CANVAS_WIDTH / 2.0;
+        frame.text(
+            Layer::Ui,
+            format!("Practice ({})", self.difficulty.name()),
+            Vector::new(x, 100.0),
+            "48px bold",
+            Color::WHITE,
+        );
+
+        let first = self
+            .selected
+            .saturating_sub(ROWS_SHOWN / 2)
+            .min(self.rows.len().saturating_sub(ROWS_SHOWN));
+        let mut top = 200.0;
+        for (i, row) in self.rows.iter().enumerate().skip(first).take(ROWS_SHOWN) {
+            let text = match row {
+                Row::Power => format!("Power: {}", self.options.power),
+                Row::Lives => format!("Lives: {}", self.options.lives),
+                Row::InfiniteLives => format!(
+                    "Infinite lives: {}",
+                    if self.options.infinite_lives {
+                        "on"
+                    } else {
+                        "off"
+                    }
+                ),
+                Row::Target(target, name) => {
+                    let (attempts, clears) = stats.get(*target);
+                    format!("{}  {}/{} cleared", name, clears, attempts)
+                }
+            };
+            let color = if i == self.selected {
+                Color::YELLOW
+            } else {
+                Color::WHITE
+            };
+            let marker = if i == self.selected { "> " } else { "" };
+            frame.text(
+                Layer::Ui,
+                format!("{}{}", marker, text),
+                Vector::new(x, top),
+                "28px bold",
+                color,
+            );
+            top += 50.0;
+        }
+
+        frame.text(
+            Layer::Ui,
+            "Left/right: change, Enter: start, Escape: back",
+            Vector::new(x, 900.0),
+            "22px bold",
+            Color::WHITE,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_stats_kept_before_versioning() {
+        let old = json!({
+            "targets": [{
+                "target": { "level": 0, "scene": 2, "attack": 1 },
+                "attempts": 4,
+                "clears": 1
+            }]
+        });
+        let stats: PracticeStats = serde_json::from_value(migrate(old)).unwrap();
+        let target = PracticeTarget {
+            difficulty: Difficulty::Normal,
+            level: 0,
+            scene: 2,
+            attack: Some(1),
+        };
+        assert_eq!(stats.get(target), (4, 1));
+    }
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+use crate::events::WorldEvent;
+
+/// Lowest and highest rank, and where a new world starts.
+const MIN_RANK: f64 = 0.0;
+const MAX_RANK: f64 = 1.0;
+const START_RANK: f64 = 0.2;
+
+/// Rank gained per second survived, and per second for each shot beyond the
+/// first the player fires at once.
+const SURVIVAL_RATE: f64 = 0.004;
+const POWER_RATE: f64 = 0.002;
+
+/// The game has no items to pick up, grazing bullets is what the player
+/// collects.
+const GRAZE_GAIN: f64 = 0.002;
+const HIT_LOSS: f64 = 0.2;
+const BOMB_LOSS: f64 = 0.08;
+
+/// How much faster bullets fly, how much sooner emitters fire again and how
+/// many more bullets they fire at the highest rank.
+const MAX_SPEED_UP: f64 = 0.2;
+const MAX_COOLDOWN_CUT: f64 = 0.2;
+const MAX_DENSITY_UP: f64 = 0.25;
+
+/// How well the player is doing, on top of the chosen difficulty. It rises
+/// while they survive, graze and keep their power, and falls when they are
+/// hit or bomb, so patterns ease off for a struggling player.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Rank {
+    value: f64,
+}
+
+impl Default for Rank {
+    fn default() -> Self {
+        Self { value: START_RANK }
+    }
+}
+
+impl Rank {
+    /// From `MIN_RANK` to `MAX_RANK`.
+    pub fn value(self) -> f64 {
+        self.value
+    }
+
+    /// `power` is `World::player_bullets`.
+    pub fn tick(&mut self, delta: f64, power: usize) {
+        let rate = SURVIVAL_RATE + POWER_RATE * power.saturating_sub(1) as f64;
+        self.add(rate * delta);
+    }
+
+    pub fn handle(&mut self, events: &[WorldEvent]) {
+        for event in events {
+            match event {
+                WorldEvent::Graze { .. } => self.add(GRAZE_GAIN),
+                WorldEvent::PlayerHit { .. } => self.add(-HIT_LOSS),
+                WorldEvent::BombUsed { .. } => self.add(-BOMB_LOSS),
+                _ => (),
+            }
+        }
+    }
+
+    fn add(&mut self, amount: f64) {
+        self.value = (self.value + amount).clamp(MIN_RANK, MAX_RANK);
+    }
+
+    /// Multiplies how fast bullets fly.
+    pub fn bullet_speed(self) -> f64 {
+        1.0 + MAX_SPEED_UP * self.value
+    }
+
+    /// Multiplies the time between volleys.
+    pub fn cooldown(self) -> f64 {
+        1.0 - MAX_COOLDOWN_CUT * self.value
+    }
+
+    /// How many bullets a volley of `bullets` has.
+    pub fn density(self, bullets: usize) -> usize {
+        (bullets as f64 * (1.0 + MAX_DENSITY_UP * self.value)).round() as usize
+    }
+}
@@ -0,0 +1,356 @@
+use gloo::{
+    storage::{LocalStorage, Storage},
+    utils::document,
+};
+use js_sys::{Array, Date};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+
+use crate::{
+    difficulty::Difficulty,
+    save::{stored, version},
+};
+
+const RECORDS_KEY: &str = "tohou.records";
+const EXPORT_FILE: &str = "tohou-save.json";
+
+/// Bumped whenever the format changes, along with a step in `migrate`.
+const RECORDS_VERSION: u64 = 1;
+
+/// Entries kept per high score table.
+const BOARD_SIZE: usize = 10;
+/// Runs kept in the history, the oldest are dropped first.
+const HISTORY_SIZE: usize = 50;
+
+/// Which table a score goes on: whole runs, or a single level of them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct BoardKey {
+    pub difficulty: Difficulty,
+    /// `None` for whole runs.
+    pub level: Option<usize>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ScoreEntry {
+    pub name: String,
+    pub score: u64,
+    /// Milliseconds since the epoch, like `Date.now()`.
+    pub date: f64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Board {
+    key: BoardKey,
+    entries: Vec<ScoreEntry>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub date: f64,
+    pub difficulty: Difficulty,
+    /// The level the run ended on, counted from 0.
+    pub level: usize,
+    pub cleared: bool,
+    pub deaths: usize,
+    /// Seconds of game time, pauses don't count.
+    pub duration: f64,
+    pub score: u64,
+}
+
+/// High scores and past runs, kept in `localStorage` and exported as is.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Records {
+    version: u64,
+    boards: Vec<Board>,
+    history: Vec<RunRecord>,
+    /// Offered again on the next name entry.
+    #[serde(default)]
+    pub last_name: String,
+    /// The stored records couldn't be read, or are from a newer version.
+    /// They are played without but never saved over.
+    #[serde(skip)]
+    read_only: bool,
+}
+
+impl Default for Records {
+    fn default() -> Self {
+        Self {
+            version: RECORDS_VERSION,
+            boards: vec![],
+            history: vec![],
+            last_name: String::new(),
+            read_only: false,
+        }
+    }
+}
+
+impl Records {
+    pub fn load() -> Self {
+        let value = match stored(RECORDS_KEY) {
+            Ok(Value::Null) => return Self::default(),
+            Ok(value) => value,
+            Err(e) => {
+                log::error!("Failed to read the records, playing without them: {}", e);
+                return Self::read_only();
+            }
+        };
+        let newer = version(&value) > RECORDS_VERSION;
+        match serde_json::from_value::<Self>(migrate(value)) {
+            Ok(records) => Self {
+                read_only: newer,
+                ..records
+            },
+            Err(e) => {
+                log::error!("Failed to read the records, playing without them: {}", e);
+                Self::read_only()
+            }
+        }
+    }
+
+    fn read_only() -> Self {
+        Self {
+            read_only: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn save(&self) {
+        if self.read_only {
+            log::warn!("Not saving over records that can't be read");
+            return;
+        }
+        if let Err(e) = LocalStorage::set(RECORDS_KEY, self) {
+            log::error!("Failed to save records: {}", e);
+        }
+    }
+
+    /// Best first.
+    pub fn board(&self, key: BoardKey) -> &[ScoreEntry] {
+        match self.boards.iter().find(|it| it.key == key) {
+            Some(board) => &board.entries,
+            None => &[],
+        }
+    }
+
+    pub fn qualifies(&self, key: BoardKey, score: u64) -> bool {
+        let board = self.board(key);
+        score > 0 && (board.len() < BOARD_SIZE || board.iter().any(|it| it.score < score))
+    }
+
+    pub fn add_score(&mut self, key: BoardKey, entry: ScoreEntry) {
+        let index = match self.boards.iter().position(|it| it.key == key) {
+            Some(index) => index,
+            None => {
+                self.boards.push(Board {
+                    key,
+                    entries: vec![],
+                });
+                self.boards.len() - 1
+            }
+        };
+        let entries = &mut self.boards[index].entries;
+        let position = entries
+            .iter()
+            .position(|it| it.score < entry.score)
+            .unwrap_or(entries.len());
+        entries.insert(position, entry);
+        entries.truncate(BOARD_SIZE);
+    }
+
+    /// Latest first.
+    pub fn history(&self) -> &[RunRecord] {
+        &self.history
+    }
+
+    pub fn add_run(&mut self, run: RunRecord) {
+        self.history.insert(0, run);
+        self.history.truncate(HISTORY_SIZE);
+    }
+
+    /// Hands the records to the browser as a file to save.
+    pub fn export(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        let parts = Array::of1(&JsValue::from_str(&json));
+        let blob = Blob::new_with_str_sequence_and_options(
+            &parts,
+            BlobPropertyBag::new().type_("application/json"),
+        )
+        .map_err(|_| "could not create the file".to_string())?;
+        let url = Url::create_object_url_with_blob(&blob)
+            .map_err(|_| "could not create the file".to_string())?;
+
+        let link = document()
+            .create_element("a")
+            .map_err(|_| "could not create a link".to_string())?
+            .unchecked_into::<HtmlAnchorElement>();
+        link.set_href(&url);
+        link.set_download(EXPORT_FILE);
+        link.click();
+        let _ = Url::revoke_object_url(&url);
+        Ok(())
+    }
+
+    /// Only files exported by `export` are taken, and only with something
+    /// in them, since they replace every record there is.
+    pub fn import(json: &str) -> Result<Self, String> {
+        let value: Value =
+            serde_json::from_str(json).map_err(|_| "the file isn't a save".to_string())?;
+        if version(&value) > RECORDS_VERSION {
+            return Err("the file is from a newer version".to_string());
+        }
+        let records: Self = serde_json::from_value(migrate(value))
+            .map_err(|_| "the file doesn't hold records".to_string())?;
+        if records.boards.is_empty() && records.history.is_empty() {
+            return Err("the file holds no records".to_string());
+        }
+        Ok(records)
+    }
+}
+
+/// Upgrades records one version at a time, anything that isn't an object is
+/// left for reading them to fail.
+fn migrate(mut records: Value) -> Value {
+    if version(&records) > RECORDS_VERSION {
+        return records;
+    }
+    // Version 0 only lacked the version.
+    if let Some(fields) = records.as_object_mut() {
+        fields.insert("version".to_string(), json!(RECORDS_VERSION));
+    }
+    records
+}
+
+/// The run being played, turned into records when it ends.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Run {
+    pub difficulty: Difficulty,
+    /// Lives lost.
+    pub deaths: usize,
+    /// Points scored in each finished level.
+    level_scores: Vec<u64>,
+    /// A debug tool changed how the run played, its scores don't go on the
+    /// high score tables.
+    #[serde(default)]
+    pub tainted: bool,
+}
+
+impl Run {
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self {
+            difficulty,
+            deaths: 0,
+            level_scores: vec![],
+            tainted: false,
+        }
+    }
+
+    /// `points` were scored in the level.
+    pub fn level_finished(&mut self, points: u64) {
+        self.level_scores.push(points);
+    }
+
+    /// `level` is where the run ended, `duration` and `score` are the
+    /// world's totals. Returns the record for the history and the scores to
+    /// offer to the high score tables.
+    pub fn finish(
+        &self,
+        level: usize,
+        cleared: bool,
+        duration: f64,
+        score: u64,
+    ) -> (RunRecord, Vec<(BoardKey, u64)>) {
+        let record = RunRecord {
+            date: Date::now(),
+            difficulty: self.difficulty,
+            level,
+            cleared,
+            deaths: self.deaths,
+            duration,
+            score,
+        };
+        let mut scores = vec![(
+            BoardKey {
+                difficulty: self.difficulty,
+                level: None,
+            },
+            score,
+        )];
+        scores.extend(self.level_scores.iter().enumerate().map(|(level, score)| {
+            (
+                BoardKey {
+                    difficulty: self.difficulty,
+                    level: Some(level),
+                },
+                *score,
+            )
+        }));
+        (record, scores)
+    }
+}
+
+/// `YYYY-MM-DD` in local time.
+pub fn format_date(date: f64) -> String {
+    let date = Date::new(&JsValue::from_f64(date));
+    format!(
+        "{}-{:02}-{:02}",
+        date.get_full_year(),
+        date.get_month() + 1,
+        date.get_date()
+    )
+}
+
+/// `m:ss`.
+pub fn format_duration(seconds: f64) -> String {
+    let seconds = seconds as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imports_records_exported_before_versioning() {
+        let records = Records::import(
+            r#"{
+                "boards": [],
+                "history": [{
+                    "date": 0.0,
+                    "difficulty": "Hard",
+                    "level": 1,
+                    "cleared": false,
+                    "deaths": 3,
+                    "duration": 90.0,
+                    "score": 1200
+                }],
+                "last_name": "Reimu"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(records.version, RECORDS_VERSION);
+        assert_eq!(records.history().len(), 1);
+        assert_eq!(records.last_name, "Reimu");
+    }
+
+    #[test]
+    fn refuses_to_import_what_isnt_records() {
+        let save = json!({ "version": 1, "settings": {}, "checkpoint": null });
+        for json in ["", "{}", "3", &save.to_string()] {
+            assert!(Records::import(json).is_err(), "imported {:?}", json);
+        }
+    }
+
+    #[test]
+    fn refuses_to_import_empty_records() {
+        let empty = serde_json::to_string(&Records::default()).unwrap();
+        assert!(Records::import(&empty).is_err());
+    }
+
+    #[test]
+    fn refuses_to_import_records_from_a_newer_version() {
+        let newer = json!({ "version": RECORDS_VERSION + 1, "boards": [], "history": [] });
+        assert!(Records::import(&newer.to_string()).is_err());
+    }
+}
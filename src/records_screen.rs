@@ -0,0 +1,281 @@
+use crate::{
+    difficulty::Difficulty,
+    geometry::Vector,
+    input::Action,
+    records::{format_date, format_duration, BoardKey, Records},
+    render::{Color, Frame, Layer, CANVAS_WIDTH},
+    screens::fill,
+};
+
+const NAME_LENGTH: usize = 12;
+/// What up and down cycle through, for entering a name without a keyboard.
+const LETTERS: &[char] = &[
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
+    'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ' ',
+];
+const DEFAULT_NAME: &str = "Player";
+/// Runs listed on the history page.
+const HISTORY_SHOWN: usize = 12;
+
+const BACKGROUND: Color = Color::rgb(60, 60, 120);
+
+fn board_name(key: BoardKey) -> String {
+    match key.level {
+        Some(level) => format!("{}, level {}", key.difficulty.name(), level + 1),
+        None => format!("{}, whole run", key.difficulty.name()),
+    }
+}
+
+#[derive(PartialEq)]
+pub enum NameEntryResult {
+    Stay,
+    Save,
+    /// The scores are thrown away.
+    Skip,
+}
+
+/// Asks for a name after a run that made it onto a high score table. Typed
+/// on a keyboard, or picked letter by letter with up, down, left and right.
+pub struct NameEntry {
+    name: String,
+    /// The tables the run made it onto, with its score on each.
+    pub scores: Vec<(BoardKey, u64)>,
+}
+
+impl NameEntry {
+    pub fn new(name: &str, scores: Vec<(BoardKey, u64)>) -> Self {
+        Self {
+            name: name.to_string(),
+            scores,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self.name.trim() {
+            "" => DEFAULT_NAME.to_string(),
+            name => name.to_string(),
+        }
+    }
+
+    /// `key` is `KeyboardEvent::key()`, so typing follows the layout.
+    pub fn key_down(&mut self, key: &str) -> NameEntryResult {
+        let mut chars = key.chars();
+        match (key, chars.next(), chars.next()) {
+            ("Enter", _, _) => return NameEntryResult::Save,
+            ("Escape", _, _) => return NameEntryResult::Skip,
+            ("Backspace", _, _) => {
+                self.name.pop();
+            }
+            (_, Some(c), None) if !c.is_control() => self.push(c),
+            _ => (),
+        }
+        NameEntryResult::Stay
+    }
+
+    pub fn action(&mut self, action: Action) -> NameEntryResult {
+        match action {
+            Action::Confirm => return NameEntryResult::Save,
+            Action::MoveRight => self.push(LETTERS[0]),
+            Action::MoveLeft => {
+                self.name.pop();
+            }
+            Action::MoveUp => self.cycle_last(1),
+            Action::MoveDown => self.cycle_last(LETTERS.len() - 1),
+            _ => (),
+        }
+        NameEntryResult::Stay
+    }
+
+    fn push(&mut self, c: char) {
+        if self.name.chars().count() < NAME_LENGTH {
+            self.name.push(c);
+        }
+    }
+
+    fn cycle_last(&mut self, step: usize) {
+        let last = match self.name.pop() {
+            Some(c) => c.to_ascii_uppercase(),
+            None => LETTERS[LETTERS.len() - 1],
+        };
+        let index = LETTERS.iter().position(|it| *it == last).unwrap_or(0);
+        self.name.push(LETTERS[(index + step) % LETTERS.len()]);
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        fill(frame, BACKGROUND);
+        let x = CANVAS_WIDTH / 2.0;
+        frame.text(
+            Layer::Ui,
+            "New high score!",
+            Vector::new(x, 150.0),
+            "48px bold",
+            Color::WHITE,
+        );
+
+        let mut top = 250.0;
+        for (key, score) in self.scores.iter() {
+            frame.text(
+                Layer::Ui,
+                format!("{}: {}", board_name(*key), score),
+                Vector::new(x, top),
+                "28px bold",
+                Color::WHITE,
+            );
+            top += 45.0;
+        }
+
+        frame.text(
+            Layer::Ui,
+            format!("{}_", self.name),
+            Vector::new(x, top + 80.0),
+            "48px bold",
+            Color::YELLOW,
+        );
+        for line in [
+            "Type your name, Enter: save, Escape: don't save",
+            "Gamepad: up/down change a letter, right adds one",
+        ] {
+            top += 40.0;
+            frame.text(
+                Layer::Ui,
+                line,
+                Vector::new(x, top + 160.0),
+                "22px bold",
+                Color::WHITE,
+            );
+        }
+    }
+}
+
+/// High score tables, one page each, followed by the run history.
+pub struct RecordsScreen {
+    page: usize,
+}
+
+impl RecordsScreen {
+    pub fn new() -> Self {
+        Self { page: 0 }
+    }
+
+    /// `None` is the history page.
+    fn pages(levels: usize) -> Vec<Option<BoardKey>> {
+        let mut pages = vec![];
+        for difficulty in Difficulty::ALL {
+            pages.push(Some(BoardKey {
+                difficulty,
+                level: None,
+            }));
+            for level in 0..levels {
+                pages.push(Some(BoardKey {
+                    difficulty,
+                    level: Some(level),
+                }));
+            }
+        }
+        pages.push(None);
+        pages
+    }
+
+    /// Returns whether the screen should be closed.
+    pub fn action(&mut self, action: Action, levels: usize) -> bool {
+        let pages = Self::pages(levels).len();
+        match action {
+            Action::MoveLeft => self.page = (self.page + pages - 1) % pages,
+            Action::MoveRight => self.page = (self.page + 1) % pages,
+            Action::Confirm | Action::Pause => return true,
+            _ => (),
+        }
+        false
+    }
+
+    pub fn draw(&self, frame: &mut Frame, records: &Records, levels: usize) {
+        fill(frame, BACKGROUND);
+        let x = CANVAS_WIDTH / 2.0;
+        let pages = Self::pages(levels);
+        let page = pages[self.page.min(pages.len() - 1)];
+
+        let (title, lines) = match page {
+            Some(key) => (
+                board_name(key),
+                records
+                    .board(key)
+                    .iter()
+                    .enumerate()
+                    .map(|(i, it)| {
+                        format!(
+                            "{}. {}  {}  {}",
+                            i + 1,
+                            it.name,
+                            it.score,
+                            format_date(it.date)
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            None => (
+                "Run history".to_string(),
+                records
+                    .history()
+                    .iter()
+                    .take(HISTORY_SHOWN)
+                    .map(|it| {
+                        let reached = if it.cleared {
+                            "cleared".to_string()
+                        } else {
+                            format!("level {}", it.level + 1)
+                        };
+                        format!(
+                            "{}  {}  {}  {} deaths  {}  {}",
+                            format_date(it.date),
+                            it.difficulty.name(),
+                            reached,
+                            it.deaths,
+                            format_duration(it.duration),
+                            it.score
+                        )
+                    })
+                    .collect(),
+            ),
+        };
+
+        frame.text(
+            Layer::Ui,
+            title,
+            Vector::new(x, 100.0),
+            "48px bold",
+            Color::WHITE,
+        );
+        let mut top = 200.0;
+        if lines.is_empty() {
+            frame.text(
+                Layer::Ui,
+                "Nothing yet",
+                Vector::new(x, top),
+                "28px bold",
+                Color::WHITE,
+            );
+        }
+        for line in lines {
+            frame.text(
+                Layer::Ui,
+                line,
+                Vector::new(x, top),
+                "26px bold",
+                Color::WHITE,
+            );
+            top += 50.0;
+        }
+
+        frame.text(
+            Layer::Ui,
+            format!(
+                "{}/{}  Left/right: change page, Enter: back",
+                self.page + 1,
+                pages.len()
+            ),
+            Vector::new(x, 900.0),
+            "22px bold",
+            Color::WHITE,
+        );
+    }
+}
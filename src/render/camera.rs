@@ -0,0 +1,139 @@
+use crate::{
+    events::WorldEvent,
+    geometry::Vector,
+    render::{Color, Frame, Layer},
+};
+
+/// Furthest the playfield is thrown around at full trauma, in pixels.
+const MAX_SHAKE: f64 = 14.0;
+/// Trauma lost per second. Shake grows with the square of trauma, so it
+/// dies down quickly at the end.
+const SHAKE_DECAY: f64 = 1.2;
+const FLASH_DECAY: f64 = 2.5;
+/// How quickly the vignette follows danger, per second.
+const VIGNETTE_SPEED: f64 = 6.0;
+
+const FLASH_COLOR: Color = Color::WHITE;
+const VIGNETTE_BANDS: usize = 8;
+const VIGNETTE_BAND_WIDTH: f64 = 12.0;
+
+/// Places the playfield on the canvas and carries the effects that move or
+/// tint it as a whole: screen shake, flashes and the danger vignette.
+pub struct Camera {
+    size: Vector,
+    offset: Vector,
+    trauma: f64,
+    flash: f64,
+    vignette: f64,
+    danger: f64,
+}
+
+impl Camera {
+    /// `size` is the playfield, which starts at the canvas' top left.
+    pub fn new(size: Vector) -> Self {
+        Self {
+            size,
+            offset: Vector::zero(),
+            trauma: 0.0,
+            flash: 0.0,
+            vignette: 0.0,
+            danger: 0.0,
+        }
+    }
+
+    /// World coordinates have the origin in the middle of the playfield.
+    pub fn to_canvas(&self, coord: Vector) -> Vector {
+        self.size * 0.5 + coord + self.offset
+    }
+
+    /// Adds to the shake, `1.0` is the most there is.
+    pub fn shake(&mut self, trauma: f64) {
+        self.trauma = (self.trauma + trauma).min(1.0);
+    }
+
+    pub fn flash(&mut self, strength: f64) {
+        self.flash = self.flash.max(strength);
+    }
+
+    /// How close the player is to being hit, from `0.0` to `1.0`.
+    pub fn set_danger(&mut self, danger: f64) {
+        self.danger = danger.clamp(0.0, 1.0);
+    }
+
+    pub fn clear(&mut self) {
+        self.offset = Vector::zero();
+        self.trauma = 0.0;
+        self.flash = 0.0;
+        self.vignette = 0.0;
+        self.danger = 0.0;
+    }
+
+    pub fn handle(&mut self, events: &[WorldEvent]) {
+        for event in events {
+            match event {
+                WorldEvent::PlayerHit { .. } => self.shake(1.0),
+                WorldEvent::BossKilled { .. } => {
+                    self.shake(0.8);
+                    self.flash(0.6);
+                }
+                WorldEvent::BombUsed { .. } => self.flash(0.9),
+                _ => (),
+            }
+        }
+    }
+
+    pub fn tick(&mut self, delta: f64) {
+        self.trauma = (self.trauma - SHAKE_DECAY * delta).max(0.0);
+        let shake = MAX_SHAKE * self.trauma * self.trauma;
+        self.offset = Vector::new(
+            (rand::random::<f64>() * 2.0 - 1.0) * shake,
+            (rand::random::<f64>() * 2.0 - 1.0) * shake,
+        );
+        self.flash = (self.flash - FLASH_DECAY * delta).max(0.0);
+        let step = (VIGNETTE_SPEED * delta).min(1.0);
+        self.vignette += (self.danger - self.vignette) * step;
+    }
+
+    /// Drawn over the rest of the playfield, not moved by the shake.
+    pub fn draw(&self, frame: &mut Frame) {
+        if self.vignette > 0.01 {
+            for i in 0..VIGNETTE_BANDS {
+                let alpha = self.vignette * 0.5 * (1.0 - i as f64 / VIGNETTE_BANDS as f64);
+                self.draw_band(
+                    frame,
+                    i as f64 * VIGNETTE_BAND_WIDTH,
+                    Color::rgba(255, 0, 0, alpha),
+                );
+            }
+        }
+        if self.flash > 0.0 {
+            frame.rect(
+                Layer::World,
+                Vector::zero(),
+                self.size,
+                Color {
+                    a: self.flash,
+                    ..FLASH_COLOR
+                },
+            );
+        }
+    }
+
+    /// A frame of `VIGNETTE_BAND_WIDTH`, `inset` from the playfield's edge.
+    fn draw_band(&self, frame: &mut Frame, inset: f64, color: Color) {
+        let width = VIGNETTE_BAND_WIDTH;
+        let inner = self.size - Vector::new(inset * 2.0, inset * 2.0);
+        let mut rect = |x, y, w, h| {
+            frame.rect(
+                Layer::World,
+                Vector::new(inset + x, inset + y),
+                Vector::new(w, h),
+                color,
+            )
+        };
+        rect(0.0, 0.0, inner.x, width);
+        rect(0.0, inner.y - width, inner.x, width);
+        rect(0.0, width, width, inner.y - width * 2.0);
+        rect(inner.x - width, width, width, inner.y - width * 2.0);
+    }
+}
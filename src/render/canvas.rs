@@ -0,0 +1,127 @@
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::textures::TextureManager;
+
+use super::{BlendMode, DrawCommand, Frame, Renderer, Sprite};
+
+/// Draws every command straight onto a 2D canvas, one call per command.
+pub struct Canvas2dRenderer {
+    context: CanvasRenderingContext2d,
+}
+
+impl Canvas2dRenderer {
+    pub fn new(context: CanvasRenderingContext2d) -> Self {
+        Self { context }
+    }
+
+    pub fn clear(&self) {
+        let canvas = self.context.canvas().unwrap();
+        self.context
+            .clear_rect(0.0, 0.0, canvas.width() as f64, canvas.height() as f64);
+    }
+
+    pub fn draw(&self, command: &DrawCommand, textures: &TextureManager) {
+        let context = &self.context;
+        match command {
+            DrawCommand::Sprite(sprite) => self.draw_sprite(sprite, textures),
+            DrawCommand::Circle {
+                center,
+                r,
+                color,
+                blend,
+            } => {
+                if *blend == BlendMode::Additive {
+                    context.save();
+                    context.set_global_composite_operation("lighter").unwrap();
+                }
+                context.begin_path();
+                context.set_fill_style(&JsValue::from_str(&color.css()));
+                context
+                    .arc(center.x, center.y, *r, 0.0, std::f64::consts::PI * 2.0)
+                    .unwrap();
+                context.fill();
+                context.close_path();
+                if *blend == BlendMode::Additive {
+                    context.restore();
+                }
+            }
+            DrawCommand::Rect {
+                top_left,
+                size,
+                color,
+            } => {
+                context.set_fill_style(&JsValue::from_str(&color.css()));
+                context.fill_rect(top_left.x, top_left.y, size.x, size.y);
+            }
+            DrawCommand::Text(text) => {
+                context.set_text_align("center");
+                context.set_font(text.font);
+                context.set_fill_style(&JsValue::from_str(&text.color.css()));
+                context
+                    .fill_text(&text.text, text.position.x, text.position.y)
+                    .unwrap();
+            }
+        }
+    }
+
+    fn draw_sprite(&self, sprite: &Sprite, textures: &TextureManager) {
+        let context = &self.context;
+        let origin = sprite.origin();
+        let transformed =
+            sprite.angle != 0.0 || sprite.alpha != 1.0 || sprite.blend != BlendMode::Normal;
+        if transformed {
+            context.save();
+            context.set_global_alpha(sprite.alpha);
+            if sprite.blend == BlendMode::Additive {
+                context.set_global_composite_operation("lighter").unwrap();
+            }
+        }
+        let (x, y) = if sprite.angle != 0.0 {
+            context
+                .translate(sprite.position.x, sprite.position.y)
+                .unwrap();
+            context.rotate(sprite.angle).unwrap();
+            (origin.x, origin.y)
+        } else {
+            (sprite.position.x + origin.x, sprite.position.y + origin.y)
+        };
+
+        let img = textures.by_id(sprite.texture);
+        match sprite.source {
+            Some(source) => context
+                .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                    img,
+                    source.x,
+                    source.y,
+                    source.w,
+                    source.h,
+                    x,
+                    y,
+                    sprite.size.x,
+                    sprite.size.y,
+                ),
+            None => context.draw_image_with_html_image_element_and_dw_and_dh(
+                img,
+                x,
+                y,
+                sprite.size.x,
+                sprite.size.y,
+            ),
+        }
+        .unwrap();
+
+        if transformed {
+            context.restore();
+        }
+    }
+}
+
+impl Renderer for Canvas2dRenderer {
+    fn render(&mut self, frame: &Frame, textures: &TextureManager) {
+        self.clear();
+        for (_, command) in frame.commands() {
+            self.draw(command, textures);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+mod camera;
+mod canvas;
+mod webgl;
+
+pub use camera::Camera;
+pub use canvas::Canvas2dRenderer;
+pub use webgl::WebGlRenderer;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    atlas::Region,
+    geometry::Vector,
+    textures::{TextureManager, TextureRegion},
+};
+
+/// Size of the canvas, the playfield plus the side panel.
+pub const CANVAS_WIDTH: f64 = 900.0;
+pub const CANVAS_HEIGHT: f64 = 1000.0;
+
+/// Draws a finished frame. The game never talks to a canvas directly, it
+/// fills a `Frame` with draw commands and hands it to one of these.
+pub trait Renderer {
+    fn render(&mut self, frame: &Frame, textures: &TextureManager);
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RendererKind {
+    Canvas2d,
+    WebGl,
+}
+
+impl RendererKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            RendererKind::Canvas2d => "Canvas 2D",
+            RendererKind::WebGl => "WebGL",
+        }
+    }
+}
+
+/// Layers are drawn in this order, whatever order the commands came in.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Layer {
+    Background,
+    World,
+    Ui,
+}
+
+impl Layer {
+    const ALL: [Layer; 3] = [Layer::Background, Layer::World, Layer::Ui];
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BlendMode {
+    Normal,
+    /// Adds up the colors, for glowing sparks.
+    Additive,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: f64,
+}
+
+impl Color {
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+    pub const YELLOW: Color = Color::rgb(255, 255, 0);
+    pub const CYAN: Color = Color::rgb(0, 255, 255);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::rgba(r, g, b, 1.0)
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: f64) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub fn css(self) -> String {
+        format!("rgba({}, {}, {}, {})", self.r, self.g, self.b, self.a)
+    }
+}
+
+/// A picture, or a frame of an atlas, drawn with its pivot on `position`
+/// and rotated around it.
+#[derive(Clone)]
+pub struct Sprite {
+    pub texture: usize,
+    pub source: Option<Region>,
+    pub position: Vector,
+    pub size: Vector,
+    pub pivot: Vector,
+    pub angle: f64,
+    pub alpha: f64,
+    pub blend: BlendMode,
+}
+
+impl Sprite {
+    pub fn new(region: &TextureRegion, position: Vector, size: Vector) -> Self {
+        Self {
+            texture: region.texture,
+            source: region.source,
+            position,
+            size,
+            pivot: region.pivot,
+            angle: 0.0,
+            alpha: 1.0,
+            blend: BlendMode::Normal,
+        }
+    }
+
+    pub fn with_angle(mut self, angle: f64) -> Self {
+        self.angle = angle;
+        self
+    }
+
+    pub fn with_alpha(mut self, alpha: f64) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    pub fn with_blend(mut self, blend: BlendMode) -> Self {
+        self.blend = blend;
+        self
+    }
+
+    /// Offset of the top left corner from `position`, before rotation.
+    pub fn origin(&self) -> Vector {
+        Vector::new(-self.pivot.x * self.size.x, -self.pivot.y * self.size.y)
+    }
+}
+
+/// Text is centered horizontally on `position`, which is on the baseline.
+#[derive(Clone)]
+pub struct Text {
+    pub text: String,
+    pub position: Vector,
+    pub font: &'static str,
+    pub color: Color,
+}
+
+#[derive(Clone)]
+pub enum DrawCommand {
+    Sprite(Sprite),
+    Circle {
+        center: Vector,
+        r: f64,
+        color: Color,
+        blend: BlendMode,
+    },
+    Rect {
+        top_left: Vector,
+        size: Vector,
+        color: Color,
+    },
+    Text(Text),
+}
+
+/// Everything drawn in one frame, in canvas pixels.
+#[derive(Default)]
+pub struct Frame {
+    layers: [Vec<DrawCommand>; 3],
+}
+
+impl Frame {
+    pub fn clear(&mut self) {
+        for layer in self.layers.iter_mut() {
+            layer.clear();
+        }
+    }
+
+    pub fn sprite(&mut self, layer: Layer, sprite: Sprite) {
+        self.push(layer, DrawCommand::Sprite(sprite));
+    }
+
+    pub fn circle(&mut self, layer: Layer, center: Vector, r: f64, color: Color) {
+        self.particle(layer, center, r, color, BlendMode::Normal);
+    }
+
+    pub fn particle(
+        &mut self,
+        layer: Layer,
+        center: Vector,
+        r: f64,
+        color: Color,
+        blend: BlendMode,
+    ) {
+        self.push(
+            layer,
+            DrawCommand::Circle {
+                center,
+                r,
+                color,
+                blend,
+            },
+        );
+    }
+
+    pub fn rect(&mut self, layer: Layer, top_left: Vector, size: Vector, color: Color) {
+        self.push(
+            layer,
+            DrawCommand::Rect {
+                top_left,
+                size,
+                color,
+            },
+        );
+    }
+
+    pub fn text(
+        &mut self,
+        layer: Layer,
+        text: impl Into<String>,
+        position: Vector,
+        font: &'static str,
+        color: Color,
+    ) {
+        self.push(
+            layer,
+            DrawCommand::Text(Text {
+                text: text.into(),
+                position,
+                font,
+                color,
+            }),
+        );
+    }
+
+    /// Commands in drawing order.
+    pub fn commands(&self) -> impl Iterator<Item = (Layer, &DrawCommand)> {
+        Layer::ALL.into_iter().flat_map(move |layer| {
+            self.layers[layer as usize]
+                .iter()
+                .map(move |it| (layer, it))
+        })
+    }
+
+    fn push(&mut self, layer: Layer, command: DrawCommand) {
+        self.layers[layer as usize].push(command);
+    }
+}
@@ -0,0 +1,379 @@
+use gloo::utils::document;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    CanvasRenderingContext2d, HtmlCanvasElement, WebGl2RenderingContext as Gl, WebGlBuffer,
+    WebGlProgram, WebGlShader, WebGlTexture,
+};
+
+use crate::{geometry::Vector, textures::TextureManager};
+
+use super::{BlendMode, Canvas2dRenderer, Color, DrawCommand, Frame, Layer, Renderer, Sprite};
+
+const VERTEX_SHADER: &str = r#"#version 300 es
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in vec2 a_uv;
+layout(location = 2) in vec4 a_color;
+uniform vec2 u_resolution;
+out vec2 v_uv;
+out vec4 v_color;
+void main() {
+    vec2 clip = a_position / u_resolution * 2.0 - 1.0;
+    gl_Position = vec4(clip.x, -clip.y, 0.0, 1.0);
+    v_uv = a_uv;
+    v_color = a_color;
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"#version 300 es
+precision mediump float;
+in vec2 v_uv;
+in vec4 v_color;
+uniform sampler2D u_texture;
+out vec4 out_color;
+void main() {
+    out_color = texture(u_texture, v_uv) * vec4(v_color.rgb * v_color.a, v_color.a);
+}
+"#;
+
+/// Position, texture coordinates and color.
+const FLOATS_PER_VERTEX: usize = 8;
+const SHAPE_SIZE: u32 = 64;
+
+/// Which texture the vertices waiting in the batch use.
+#[derive(Clone, Copy, PartialEq)]
+enum BatchTexture {
+    Image(usize),
+    Shape,
+}
+
+/// Collects quads into one vertex buffer and only issues a draw call when
+/// the texture changes, so a screen full of bullets costs a few calls.
+///
+/// Text has no good WebGL equivalent, so the `Ui` layer and all text are
+/// drawn by a 2D renderer on an overlay canvas stacked on top.
+pub struct WebGlRenderer {
+    gl: Gl,
+    buffer: WebGlBuffer,
+    /// A white circle, also used for flat rectangles by sampling its middle.
+    shape: WebGlTexture,
+    textures: Vec<Option<WebGlTexture>>,
+    vertices: Vec<f32>,
+    bytes: Vec<u8>,
+    batch: Option<(BatchTexture, BlendMode)>,
+    overlay: Canvas2dRenderer,
+}
+
+impl WebGlRenderer {
+    pub fn new(
+        canvas: &HtmlCanvasElement,
+        overlay: CanvasRenderingContext2d,
+    ) -> Result<Self, String> {
+        let gl = canvas
+            .get_context("webgl2")
+            .ok()
+            .flatten()
+            .ok_or_else(|| "WebGL2 is not supported".to_string())?
+            .dyn_into::<Gl>()
+            .map_err(|_| "WebGL2 context has the wrong type".to_string())?;
+        let size = Vector::new(canvas.width() as f64, canvas.height() as f64);
+
+        let program = Self::link_program(&gl)?;
+        gl.use_program(Some(&program));
+        let resolution = gl.get_uniform_location(&program, "u_resolution");
+        gl.uniform2f(resolution.as_ref(), size.x as f32, size.y as f32);
+
+        let vao = gl
+            .create_vertex_array()
+            .ok_or("Failed to create vertex array")?;
+        gl.bind_vertex_array(Some(&vao));
+        let buffer = gl.create_buffer().ok_or("Failed to create buffer")?;
+        gl.bind_buffer(Gl::ARRAY_BUFFER, Some(&buffer));
+        let stride = (FLOATS_PER_VERTEX * 4) as i32;
+        for (location, size, offset) in [(0, 2, 0), (1, 2, 2), (2, 4, 4)] {
+            gl.enable_vertex_attrib_array(location);
+            gl.vertex_attrib_pointer_with_i32(location, size, Gl::FLOAT, false, stride, offset * 4);
+        }
+
+        gl.enable(Gl::BLEND);
+        gl.blend_func(Gl::ONE, Gl::ONE_MINUS_SRC_ALPHA);
+        gl.pixel_storei(Gl::UNPACK_PREMULTIPLY_ALPHA_WEBGL, 1);
+        gl.viewport(0, 0, size.x as i32, size.y as i32);
+        gl.clear_color(0.0, 0.0, 0.0, 0.0);
+
+        let shape = Self::shape_texture(&gl)?;
+
+        Ok(Self {
+            gl,
+            buffer,
+            shape,
+            textures: vec![],
+            vertices: vec![],
+            bytes: vec![],
+            batch: None,
+            overlay: Canvas2dRenderer::new(overlay),
+        })
+    }
+
+    fn link_program(gl: &Gl) -> Result<WebGlProgram, String> {
+        let vertex = Self::compile_shader(gl, Gl::VERTEX_SHADER, VERTEX_SHADER)?;
+        let fragment = Self::compile_shader(gl, Gl::FRAGMENT_SHADER, FRAGMENT_SHADER)?;
+        let program = gl.create_program().ok_or("Failed to create program")?;
+        gl.attach_shader(&program, &vertex);
+        gl.attach_shader(&program, &fragment);
+        gl.link_program(&program);
+
+        if gl
+            .get_program_parameter(&program, Gl::LINK_STATUS)
+            .as_bool()
+            .unwrap_or(false)
+        {
+            Ok(program)
+        } else {
+            Err(gl.get_program_info_log(&program).unwrap_or_default())
+        }
+    }
+
+    fn compile_shader(gl: &Gl, typ: u32, source: &str) -> Result<WebGlShader, String> {
+        let shader = gl.create_shader(typ).ok_or("Failed to create shader")?;
+        gl.shader_source(&shader, source);
+        gl.compile_shader(&shader);
+
+        if gl
+            .get_shader_parameter(&shader, Gl::COMPILE_STATUS)
+            .as_bool()
+            .unwrap_or(false)
+        {
+            Ok(shader)
+        } else {
+            Err(gl.get_shader_info_log(&shader).unwrap_or_default())
+        }
+    }
+
+    fn shape_texture(gl: &Gl) -> Result<WebGlTexture, String> {
+        let canvas = document()
+            .create_element("canvas")
+            .map_err(|_| "Failed to create canvas")?
+            .unchecked_into::<HtmlCanvasElement>();
+        canvas.set_width(SHAPE_SIZE);
+        canvas.set_height(SHAPE_SIZE);
+        let context = canvas
+            .get_context("2d")
+            .ok()
+            .flatten()
+            .ok_or("Failed to get 2d context")?
+            .unchecked_into::<CanvasRenderingContext2d>();
+        let r = SHAPE_SIZE as f64 / 2.0;
+        context.set_fill_style(&JsValue::from_str("white"));
+        context.begin_path();
+        context
+            .arc(r, r, r, 0.0, std::f64::consts::PI * 2.0)
+            .unwrap();
+        context.fill();
+
+        let texture = Self::create_texture(gl)?;
+        gl.tex_image_2d_with_u32_and_u32_and_html_canvas_element(
+            Gl::TEXTURE_2D,
+            0,
+            Gl::RGBA as i32,
+            Gl::RGBA,
+            Gl::UNSIGNED_BYTE,
+            &canvas,
+        )
+        .map_err(|_| "Failed to upload shape texture")?;
+        Ok(texture)
+    }
+
+    fn create_texture(gl: &Gl) -> Result<WebGlTexture, String> {
+        let texture = gl.create_texture().ok_or("Failed to create texture")?;
+        gl.bind_texture(Gl::TEXTURE_2D, Some(&texture));
+        for (name, value) in [
+            (Gl::TEXTURE_MIN_FILTER, Gl::LINEAR),
+            (Gl::TEXTURE_MAG_FILTER, Gl::LINEAR),
+            (Gl::TEXTURE_WRAP_S, Gl::CLAMP_TO_EDGE),
+            (Gl::TEXTURE_WRAP_T, Gl::CLAMP_TO_EDGE),
+        ] {
+            gl.tex_parameteri(Gl::TEXTURE_2D, name, value as i32);
+        }
+        Ok(texture)
+    }
+
+    /// Uploads images the first time they are drawn.
+    fn image_texture(&mut self, id: usize, textures: &TextureManager) -> Option<&WebGlTexture> {
+        if self.textures.len() <= id {
+            self.textures.resize(id + 1, None);
+        }
+        if self.textures[id].is_none() {
+            let texture = Self::create_texture(&self.gl).ok()?;
+            self.gl
+                .tex_image_2d_with_u32_and_u32_and_html_image_element(
+                    Gl::TEXTURE_2D,
+                    0,
+                    Gl::RGBA as i32,
+                    Gl::RGBA,
+                    Gl::UNSIGNED_BYTE,
+                    textures.by_id(id),
+                )
+                .ok()?;
+            self.textures[id] = Some(texture);
+        }
+        self.textures[id].as_ref()
+    }
+
+    fn release_unloaded(&mut self, textures: &TextureManager) {
+        for (id, texture) in self.textures.iter_mut().enumerate() {
+            if texture.is_some() && !textures.is_loaded(id) {
+                self.gl.delete_texture(texture.take().as_ref());
+            }
+        }
+    }
+
+    fn use_texture(&mut self, texture: BatchTexture, blend: BlendMode, textures: &TextureManager) {
+        if self.batch == Some((texture, blend)) {
+            return;
+        }
+        self.flush();
+        let previous_blend = self.batch.map(|(_, blend)| blend);
+        self.batch = Some((texture, blend));
+        if previous_blend != Some(blend) {
+            match blend {
+                BlendMode::Normal => self.gl.blend_func(Gl::ONE, Gl::ONE_MINUS_SRC_ALPHA),
+                BlendMode::Additive => self.gl.blend_func(Gl::ONE, Gl::ONE),
+            }
+        }
+        let gl_texture = match texture {
+            BatchTexture::Image(id) => self.image_texture(id, textures).cloned(),
+            BatchTexture::Shape => Some(self.shape.clone()),
+        };
+        self.gl.bind_texture(Gl::TEXTURE_2D, gl_texture.as_ref());
+    }
+
+    fn flush(&mut self) {
+        if self.vertices.is_empty() {
+            return;
+        }
+        self.bytes.clear();
+        self.bytes
+            .extend(self.vertices.iter().flat_map(|it| it.to_ne_bytes()));
+        self.gl.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.buffer));
+        self.gl
+            .buffer_data_with_u8_array(Gl::ARRAY_BUFFER, &self.bytes, Gl::STREAM_DRAW);
+        self.gl.draw_arrays(
+            Gl::TRIANGLES,
+            0,
+            (self.vertices.len() / FLOATS_PER_VERTEX) as i32,
+        );
+        self.vertices.clear();
+    }
+
+    /// Two triangles covering `size` from `origin` relative to `position`,
+    /// rotated around `position` by `angle`.
+    fn quad(
+        &mut self,
+        position: Vector,
+        origin: Vector,
+        size: Vector,
+        angle: f64,
+        uv: [f32; 4],
+        color: Color,
+    ) {
+        let (sin, cos) = angle.sin_cos();
+        let corner = |dx: f64, dy: f64| {
+            let x = origin.x + dx * size.x;
+            let y = origin.y + dy * size.y;
+            (
+                (position.x + x * cos - y * sin) as f32,
+                (position.y + x * sin + y * cos) as f32,
+            )
+        };
+        let [u0, v0, u1, v1] = uv;
+        let corners = [
+            (corner(0.0, 0.0), u0, v0),
+            (corner(1.0, 0.0), u1, v0),
+            (corner(1.0, 1.0), u1, v1),
+            (corner(0.0, 0.0), u0, v0),
+            (corner(1.0, 1.0), u1, v1),
+            (corner(0.0, 1.0), u0, v1),
+        ];
+        let rgba = [
+            color.r as f32 / 255.0,
+            color.g as f32 / 255.0,
+            color.b as f32 / 255.0,
+            color.a as f32,
+        ];
+        for ((x, y), u, v) in corners {
+            self.vertices.extend([x, y, u, v]);
+            self.vertices.extend(rgba);
+        }
+    }
+
+    /// A shape from the shape texture, centered on `center`.
+    fn shape(&mut self, center: Vector, size: Vector, uv: [f32; 4], color: Color) {
+        self.quad(center, size * -0.5, size, 0.0, uv, color);
+    }
+
+    fn draw_sprite(&mut self, sprite: &Sprite, textures: &TextureManager) {
+        self.use_texture(BatchTexture::Image(sprite.texture), sprite.blend, textures);
+        let uv = match sprite.source {
+            Some(source) => {
+                let img = textures.by_id(sprite.texture);
+                let w = img.width() as f64;
+                let h = img.height() as f64;
+                [
+                    (source.x / w) as f32,
+                    (source.y / h) as f32,
+                    ((source.x + source.w) / w) as f32,
+                    ((source.y + source.h) / h) as f32,
+                ]
+            }
+            None => [0.0, 0.0, 1.0, 1.0],
+        };
+        self.quad(
+            sprite.position,
+            sprite.origin(),
+            sprite.size,
+            sprite.angle,
+            uv,
+            Color {
+                a: sprite.alpha,
+                ..Color::WHITE
+            },
+        );
+    }
+}
+
+impl Renderer for WebGlRenderer {
+    fn render(&mut self, frame: &Frame, textures: &TextureManager) {
+        self.release_unloaded(textures);
+        self.gl.clear(Gl::COLOR_BUFFER_BIT);
+        self.overlay.clear();
+        self.batch = None;
+
+        for (layer, command) in frame.commands() {
+            match command {
+                _ if layer == Layer::Ui => self.overlay.draw(command, textures),
+                DrawCommand::Text(_) => self.overlay.draw(command, textures),
+                DrawCommand::Sprite(sprite) => self.draw_sprite(sprite, textures),
+                DrawCommand::Circle {
+                    center,
+                    r,
+                    color,
+                    blend,
+                } => {
+                    self.use_texture(BatchTexture::Shape, *blend, textures);
+                    let size = Vector::new(r * 2.0, r * 2.0);
+                    self.shape(*center, size, [0.0, 0.0, 1.0, 1.0], *color);
+                }
+                DrawCommand::Rect {
+                    top_left,
+                    size,
+                    color,
+                } => {
+                    self.use_texture(BatchTexture::Shape, BlendMode::Normal, textures);
+                    let center = *top_left + *size * 0.5;
+                    self.shape(center, *size, [0.5, 0.5, 0.5, 0.5], *color);
+                }
+            }
+        }
+        self.flush();
+    }
+}
@@ -0,0 +1,195 @@
+use gloo::storage::{errors::StorageError, LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{rank::Rank, records::Run, settings::Settings};
+
+const SAVE_KEY: &str = "tohou.save";
+
+/// Bumped whenever the format changes, along with a step in `migrate` that
+/// brings older saves up to date.
+const SAVE_VERSION: u64 = 1;
+
+/// Before the save file, settings were stored on their own under this key.
+const LEGACY_SETTINGS_KEY: &str = "tohou.settings";
+
+/// Where a run stood when its current level started. Kept to try the level
+/// again, and in the save file to continue the run after a reload.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub level: usize,
+    /// `World::player_bullets`.
+    pub power: usize,
+    pub lives: usize,
+    pub score: u64,
+    /// Saves from before rank existed start from the default one.
+    #[serde(default)]
+    pub rank: Rank,
+    /// Game time played so far.
+    pub time: f64,
+    pub run: Run,
+}
+
+/// Everything besides the records that survives a reload.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveFile {
+    version: u64,
+    pub settings: Settings,
+    /// The run to continue, if one was left unfinished.
+    pub checkpoint: Option<Checkpoint>,
+}
+
+impl Default for SaveFile {
+    fn default() -> Self {
+        Self {
+            version: SAVE_VERSION,
+            settings: Settings::default(),
+            checkpoint: None,
+        }
+    }
+}
+
+impl SaveFile {
+    /// A save that can't be read is logged and played without, but it stays
+    /// in storage, see `store`.
+    pub fn load() -> Self {
+        let value = match stored(SAVE_KEY) {
+            Ok(value) => value,
+            Err(e) => {
+                log::error!("Failed to read the save, playing without it: {}", e);
+                return Self::default();
+            }
+        };
+        let outdated = version(&value) < SAVE_VERSION;
+        match serde_json::from_value::<Self>(migrate(value, legacy_settings())) {
+            Ok(save) => {
+                if outdated {
+                    save.save();
+                    LocalStorage::delete(LEGACY_SETTINGS_KEY);
+                }
+                save
+            }
+            Err(e) => {
+                log::error!("Failed to read the save, playing without it: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    fn save(&self) {
+        if let Err(e) = LocalStorage::set(SAVE_KEY, self) {
+            log::error!("Failed to save: {}", e);
+        }
+    }
+
+    pub fn store_settings(settings: &Settings) {
+        store("settings", settings);
+    }
+
+    pub fn store_checkpoint(checkpoint: Option<Checkpoint>) {
+        store("checkpoint", checkpoint);
+    }
+}
+
+/// What is stored under `key`, `Null` when there is nothing yet.
+pub fn stored(key: &str) -> Result<Value, StorageError> {
+    match LocalStorage::get::<Value>(key) {
+        Err(StorageError::KeyNotFound(_)) => Ok(Value::Null),
+        result => result,
+    }
+}
+
+fn legacy_settings() -> Option<Value> {
+    LocalStorage::get::<Value>(LEGACY_SETTINGS_KEY).ok()
+}
+
+/// Replaces one field of the stored save and leaves the rest as it is, so a
+/// save that can't be read, like one from a newer version, is never
+/// overwritten with defaults. Nothing is written if it isn't JSON at all.
+fn store(field: &str, value: impl Serialize) {
+    let mut save = match stored(SAVE_KEY) {
+        Ok(save) => migrate(save, legacy_settings()),
+        Err(e) => {
+            log::error!("Not saving over a save that can't be read: {}", e);
+            return;
+        }
+    };
+    let value = match serde_json::to_value(value) {
+        Ok(value) => value,
+        Err(e) => {
+            log::error!("Failed to save: {}", e);
+            return;
+        }
+    };
+    match save.as_object_mut() {
+        Some(fields) => {
+            fields.insert(field.to_string(), value);
+        }
+        None => {
+            log::error!("Not saving over a save that isn't an object");
+            return;
+        }
+    }
+    if let Err(e) = LocalStorage::set(SAVE_KEY, &save) {
+        log::error!("Failed to save: {}", e);
+    }
+}
+
+/// Upgrades a save one version at a time. Fields that are missing after
+/// that get their defaults when the save is read. `legacy_settings` are
+/// what was stored before there was a save file.
+fn migrate(mut save: Value, legacy_settings: Option<Value>) -> Value {
+    let version = version(&save);
+    if version > SAVE_VERSION {
+        log::warn!(
+            "The save is from a newer version ({}), reading what is understood",
+            version
+        );
+        return save;
+    }
+
+    if version < 1 {
+        // There was no save file, only the settings.
+        save = json!({});
+        if let Some(settings) = legacy_settings {
+            save["settings"] = settings;
+        }
+    }
+
+    save["version"] = json!(SAVE_VERSION);
+    save
+}
+
+/// Saves from before versioning count as version 0.
+pub fn version(save: &Value) -> u64 {
+    save.get("version").and_then(Value::as_u64).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_legacy_settings_into_a_save() {
+        let legacy = json!({ "music_volume": 0.5 });
+        let save = migrate(Value::Null, Some(legacy.clone()));
+        assert_eq!(save, json!({ "version": SAVE_VERSION, "settings": legacy }));
+
+        let save: SaveFile = serde_json::from_value(save).unwrap();
+        assert_eq!(save.settings.music_volume, 0.5);
+        assert!(save.checkpoint.is_none());
+    }
+
+    #[test]
+    fn migrates_a_version_0_save_without_legacy_settings() {
+        let save = migrate(json!({ "unrelated": true }), None);
+        assert_eq!(save, json!({ "version": SAVE_VERSION }));
+    }
+
+    #[test]
+    fn leaves_newer_saves_alone() {
+        let newer = json!({ "version": SAVE_VERSION + 1, "settings": 3 });
+        assert_eq!(migrate(newer.clone(), None), newer);
+    }
+}
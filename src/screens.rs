@@ -0,0 +1,333 @@
+use crate::{
+    geometry::Vector,
+    input::Action,
+    practice::PracticeScreen,
+    records_screen::{NameEntry, RecordsScreen},
+    render::{Color, Frame, Layer, CANVAS_HEIGHT, CANVAS_WIDTH},
+};
+
+/// Everything that can be on the screen stack. Only the top screen gets
+/// input; `Playing` further down the stack keeps the world visible but
+/// frozen under the menus drawn on top of it.
+pub enum Screen {
+    Loading,
+    Title,
+    Playing,
+    Controls,
+    Credits,
+    Menu(Menu),
+    NameEntry(NameEntry),
+    Records(RecordsScreen),
+    Practice(PracticeScreen),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum GameOverKind {
+    Lost,
+    Won,
+    LevelFinished,
+    PracticeCleared,
+    PracticeFailed,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MenuKind {
+    /// `can_continue` when a run was left unfinished.
+    Main {
+        can_continue: bool,
+    },
+    Pause,
+    Options,
+    Records,
+    Results(GameOverKind),
+    /// Some assets could not be downloaded.
+    LoadFailed,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MenuItem {
+    Continue,
+    Start,
+    Difficulty,
+    Practice,
+    Resume,
+    Restart,
+    Retry,
+    NextLevel,
+    Options,
+    Records,
+    HighScores,
+    ExportSave,
+    ImportSave,
+    Controls,
+    Credits,
+    QuitToTitle,
+    RetryLoading,
+    MusicVolume,
+    SoundVolume,
+    StickMode,
+    Renderer,
+    Back,
+}
+
+impl MenuItem {
+    /// Items whose value is changed with left and right.
+    pub fn is_adjustable(self) -> bool {
+        matches!(
+            self,
+            MenuItem::Difficulty
+                | MenuItem::MusicVolume
+                | MenuItem::SoundVolume
+                | MenuItem::StickMode
+                | MenuItem::Renderer
+        )
+    }
+}
+
+impl MenuKind {
+    fn items(self) -> &'static [MenuItem] {
+        match self {
+            MenuKind::Main { can_continue: true } => &[
+                MenuItem::Continue,
+                MenuItem::Start,
+                MenuItem::Difficulty,
+                MenuItem::Practice,
+                MenuItem::Records,
+                MenuItem::Options,
+                MenuItem::Credits,
+            ],
+            MenuKind::Main {
+                can_continue: false,
+            } => &[
+                MenuItem::Start,
+                MenuItem::Difficulty,
+                MenuItem::Practice,
+                MenuItem::Records,
+                MenuItem::Options,
+                MenuItem::Credits,
+            ],
+            MenuKind::Pause => &[
+                MenuItem::Resume,
+                MenuItem::Restart,
+                MenuItem::Options,
+                MenuItem::QuitToTitle,
+            ],
+            MenuKind::Options => &[
+                MenuItem::MusicVolume,
+                MenuItem::SoundVolume,
+                MenuItem::StickMode,
+                MenuItem::Renderer,
+                MenuItem::Controls,
+                MenuItem::Back,
+            ],
+            MenuKind::Records => &[
+                MenuItem::HighScores,
+                MenuItem::ExportSave,
+                MenuItem::ImportSave,
+                MenuItem::Back,
+            ],
+            MenuKind::Results(GameOverKind::Lost) => &[MenuItem::Retry, MenuItem::QuitToTitle],
+            MenuKind::Results(GameOverKind::LevelFinished) => {
+                &[MenuItem::NextLevel, MenuItem::QuitToTitle]
+            }
+            MenuKind::Results(GameOverKind::Won) => &[MenuItem::Credits, MenuItem::QuitToTitle],
+            MenuKind::Results(GameOverKind::PracticeCleared | GameOverKind::PracticeFailed) => {
+                &[MenuItem::Retry, MenuItem::Practice, MenuItem::QuitToTitle]
+            }
+            MenuKind::LoadFailed => &[MenuItem::RetryLoading],
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            MenuKind::Main { .. } | MenuKind::Options | MenuKind::Records => {
+                Color::rgba(100, 100, 255, 0.9)
+            }
+            MenuKind::Pause => Color::rgba(40, 40, 40, 0.7),
+            MenuKind::Results(GameOverKind::Lost | GameOverKind::PracticeFailed)
+            | MenuKind::LoadFailed => Color::rgba(255, 100, 100, 0.85),
+            MenuKind::Results(_) => Color::rgba(100, 255, 100, 0.85),
+        }
+    }
+}
+
+pub enum MenuInput {
+    Select(MenuItem),
+    Adjust(MenuItem, f64),
+    Back,
+}
+
+/// How long results take to fade in over the world, in seconds.
+const RESULTS_FADE_TIME: f64 = 0.8;
+
+/// Where menus start, and the room each line of the title and each item
+/// takes. Items are drawn on their baseline, the text sits above it.
+const MENU_TOP: f64 = 300.0;
+const TITLE_LINE_HEIGHT: f64 = 70.0;
+const ITEM_HEIGHT: f64 = 60.0;
+const ITEM_ASCENT: f64 = 42.0;
+
+pub struct Menu {
+    pub kind: MenuKind,
+    selected: usize,
+    time: f64,
+}
+
+impl Menu {
+    pub fn new(kind: MenuKind) -> Self {
+        Self {
+            kind,
+            selected: 0,
+            time: 0.0,
+        }
+    }
+
+    pub fn tick(&mut self, delta: f64) {
+        self.time += delta;
+    }
+
+    /// Results fade in, the other menus show up at once.
+    fn opacity(&self) -> f64 {
+        match self.kind {
+            MenuKind::Results(_) => (self.time / RESULTS_FADE_TIME).min(1.0),
+            _ => 1.0,
+        }
+    }
+
+    pub fn navigate(&mut self, action: Action) -> Option<MenuInput> {
+        // A button still held from playing must not skip past the results.
+        if self.opacity() < 1.0 {
+            return None;
+        }
+        let items = self.kind.items();
+        let item = items[self.selected];
+        match action {
+            Action::MoveUp => self.selected = (self.selected + items.len() - 1) % items.len(),
+            Action::MoveDown => self.selected = (self.selected + 1) % items.len(),
+            Action::MoveLeft if item.is_adjustable() => return Some(MenuInput::Adjust(item, -1.0)),
+            Action::MoveRight if item.is_adjustable() => return Some(MenuInput::Adjust(item, 1.0)),
+            Action::Confirm => return Some(MenuInput::Select(item)),
+            Action::Pause => return Some(MenuInput::Back),
+            _ => (),
+        }
+        None
+    }
+
+    /// Picks the item at `point` on the canvas, adjusting items from their
+    /// left or right half. `title_lines` is the length of the title drawn.
+    pub fn tap(&mut self, point: Vector, title_lines: usize) -> Option<MenuInput> {
+        if self.opacity() < 1.0 {
+            return None;
+        }
+        let items = self.kind.items();
+        let top = Self::items_top(title_lines) - ITEM_ASCENT;
+        let index = ((point.y - top) / ITEM_HEIGHT).floor();
+        if index < 0.0 || index >= items.len() as f64 {
+            return None;
+        }
+        self.selected = index as usize;
+        let item = items[self.selected];
+        if !item.is_adjustable() {
+            return Some(MenuInput::Select(item));
+        }
+        let direction = if point.x < CANVAS_WIDTH / 2.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        Some(MenuInput::Adjust(item, direction))
+    }
+
+    fn items_top(title_lines: usize) -> f64 {
+        MENU_TOP + TITLE_LINE_HEIGHT * title_lines as f64 + 50.0
+    }
+
+    /// `title` is drawn above the items, `label` turns an item into text.
+    pub fn draw(&self, frame: &mut Frame, title: &[String], label: impl Fn(MenuItem) -> String) {
+        let opacity = self.opacity();
+        let fade = |color: Color| Color {
+            a: color.a * opacity,
+            ..color
+        };
+        fill(frame, fade(self.kind.color()));
+
+        let mut top = MENU_TOP;
+        for line in title {
+            frame.text(
+                Layer::Ui,
+                line.as_str(),
+                Vector::new(CANVAS_WIDTH / 2.0, top),
+                "48px bold",
+                fade(Color::WHITE),
+            );
+            top += TITLE_LINE_HEIGHT;
+        }
+
+        let mut top = Self::items_top(title.len());
+        for (i, item) in self.kind.items().iter().enumerate() {
+            let (text, color) = if i == self.selected {
+                (format!("> {} <", label(*item)), Color::YELLOW)
+            } else {
+                (label(*item), Color::WHITE)
+            };
+            frame.text(
+                Layer::Ui,
+                text,
+                Vector::new(CANVAS_WIDTH / 2.0, top),
+                "36px bold",
+                fade(color),
+            );
+            top += ITEM_HEIGHT;
+        }
+    }
+}
+
+/// A full screen of centered text, used for the screens without items.
+pub fn draw_text_screen(frame: &mut Frame, color: Color, lines: &[String]) {
+    fill(frame, color);
+    let mut top = 500.0 - 50.0 * (lines.len() as f64 - 1.0);
+    for line in lines {
+        frame.text(
+            Layer::Ui,
+            line.as_str(),
+            Vector::new(CANVAS_WIDTH / 2.0, top),
+            "48px bold",
+            Color::WHITE,
+        );
+        top += 100.0;
+    }
+}
+
+/// The loading screen, with a bar filling up as downloads finish.
+pub fn draw_loading(frame: &mut Frame, color: Color, finished: usize, total: usize) {
+    draw_text_screen(
+        frame,
+        color,
+        &[format!("Loading... {}/{}", finished, total)],
+    );
+
+    let size = Vector::new(CANVAS_WIDTH * 0.5, 24.0);
+    let top_left = Vector::new((CANVAS_WIDTH - size.x) / 2.0, 560.0);
+    let fraction = if total == 0 {
+        0.0
+    } else {
+        finished as f64 / total as f64
+    };
+    frame.rect(Layer::Ui, top_left, size, Color::rgba(0, 0, 0, 0.4));
+    frame.rect(
+        Layer::Ui,
+        top_left,
+        Vector::new(size.x * fraction, size.y),
+        Color::WHITE,
+    );
+}
+
+/// Covers the whole canvas, hiding or tinting the world under the menus.
+pub fn fill(frame: &mut Frame, color: Color) {
+    frame.rect(
+        Layer::Ui,
+        Vector::zero(),
+        Vector::new(CANVAS_WIDTH, CANVAS_HEIGHT),
+        color,
+    );
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{difficulty::Difficulty, render::RendererKind, save::SaveFile};
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub music_volume: f64,
+    pub sound_volume: f64,
+    pub renderer: RendererKind,
+    /// What the next run started from the title is played on.
+    pub difficulty: Difficulty,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            music_volume: 1.0,
+            sound_volume: 1.0,
+            renderer: RendererKind::WebGl,
+            difficulty: Difficulty::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Settings live in the save file, next to the run to continue.
+    pub fn load() -> Self {
+        SaveFile::load().settings
+    }
+
+    pub fn save(&self) {
+        SaveFile::store_settings(self);
+    }
+}
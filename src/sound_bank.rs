@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use crate::{
+    assets::{AssetList, Assets, SoundId},
+    audio::AudioManager,
+    events::{EventKind, WorldEvent},
+};
+
+#[derive(Clone)]
+pub struct SoundEffect {
+    pub sound: SoundId,
+    pub volume: f64,
+    /// Minimal time in seconds between two plays of this effect.
+    pub cooldown: f64,
+}
+
+impl SoundEffect {
+    pub fn new(sound: SoundId, volume: f64, cooldown: f64) -> Self {
+        Self {
+            sound,
+            volume,
+            cooldown,
+        }
+    }
+}
+
+/// Per level replacement of the default sound of an event kind. `None`
+/// silences the event for that level.
+pub type SoundOverride = (EventKind, Option<SoundEffect>);
+
+/// Maps world events to sound effects. Events of the same kind that happen
+/// in one frame are played once, and every kind has its own cooldown so a
+/// dense pattern does not turn into noise.
+pub struct SoundBank {
+    effects: HashMap<EventKind, SoundEffect>,
+    last_played: HashMap<EventKind, f64>,
+    half_width: f64,
+    time: f64,
+    volume: f64,
+}
+
+impl SoundBank {
+    pub fn new(playfield_width: f64, assets: &Assets) -> Self {
+        let shoot = assets.sound("shoot");
+        let shoot_2 = assets.sound("shoot_2");
+        let shoot_3 = assets.sound("shoot_3");
+        let effects = [
+            (EventKind::PlayerShot, SoundEffect::new(shoot_3, 0.1, 0.0)),
+            (
+                EventKind::BulletCancelled,
+                SoundEffect::new(shoot, 0.3, 0.05),
+            ),
+            (EventKind::EnemyHit, SoundEffect::new(shoot_2, 0.3, 0.05)),
+            (EventKind::EnemyKilled, SoundEffect::new(shoot, 0.6, 0.1)),
+            (EventKind::Graze, SoundEffect::new(shoot_3, 0.05, 0.1)),
+            (EventKind::BombUsed, SoundEffect::new(shoot, 0.9, 0.0)),
+            (EventKind::PlayerHit, SoundEffect::new(shoot_2, 0.8, 0.0)),
+        ];
+
+        Self {
+            effects: effects.into_iter().collect(),
+            last_played: HashMap::new(),
+            half_width: playfield_width / 2.0,
+            time: 0.0,
+            volume: 1.0,
+        }
+    }
+
+    /// The sounds played unless a level says otherwise.
+    pub fn assets(&self) -> AssetList {
+        let mut assets = AssetList::default();
+        for effect in self.effects.values() {
+            assets.add_sound(effect.sound);
+        }
+        assets
+    }
+
+    pub fn set_volume(&mut self, volume: f64) {
+        self.volume = volume;
+    }
+
+    pub fn tick(&mut self, delta: f64) {
+        self.time += delta;
+    }
+
+    pub fn play(
+        &mut self,
+        events: &[WorldEvent],
+        overrides: &[SoundOverride],
+        audio: &AudioManager,
+    ) {
+        let mut played = vec![];
+        for event in events {
+            let kind = event.kind();
+            if played.contains(&kind) {
+                continue;
+            }
+            played.push(kind);
+
+            let effect = match overrides.iter().find(|(it, _)| *it == kind) {
+                Some((_, effect)) => effect.as_ref(),
+                None => self.effects.get(&kind),
+            };
+            let effect = match effect {
+                Some(effect) => effect,
+                None => continue,
+            };
+
+            if let Some(last) = self.last_played.get(&kind) {
+                if self.time - last < effect.cooldown {
+                    continue;
+                }
+            }
+            self.last_played.insert(kind, self.time);
+
+            let pan = match event.position() {
+                Some(position) => (position.x / self.half_width).clamp(-1.0, 1.0),
+                None => 0.0,
+            };
+            audio.play_panned(effect.sound, effect.volume * self.volume, pan);
+        }
+    }
+}
@@ -2,25 +2,104 @@ use std::collections::HashMap;
 
 use web_sys::HtmlImageElement;
 
+use crate::{
+    assets::{Assets, TextureId},
+    atlas::{Atlas, Region},
+    geometry::Vector,
+};
+
+/// The part of a texture a sprite is drawn from.
+#[derive(Clone, Copy)]
+pub struct TextureRegion {
+    pub texture: usize,
+    /// `None` for a whole image.
+    pub source: Option<Region>,
+    pub size: Vector,
+    pub pivot: Vector,
+}
+
 pub struct TextureManager {
-    textures: Vec<HtmlImageElement>,
-    names: HashMap<String, usize>,
+    /// Unloaded images leave a gap, so the index of an image never changes.
+    textures: Vec<Option<HtmlImageElement>>,
+    regions: HashMap<TextureId, TextureRegion>,
 }
 
 impl TextureManager {
     pub fn new() -> Self {
         Self {
             textures: vec![],
-            names: HashMap::new(),
+            regions: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, assets: &Assets, id: TextureId, img: HtmlImageElement) {
+        let size = Vector::new(img.width() as f64, img.height() as f64);
+        self.textures.push(Some(img));
+        let pivot = assets.texture_entry(id).pivot.unwrap_or([0.5, 0.5]);
+        self.regions.insert(
+            id,
+            TextureRegion {
+                texture: self.textures.len() - 1,
+                source: None,
+                size,
+                pivot: Vector::new(pivot[0], pivot[1]),
+            },
+        );
+    }
+
+    /// Frames that aren't in the manifest are left out.
+    pub fn insert_atlas(&mut self, assets: &Assets, atlas: Atlas, img: HtmlImageElement) {
+        self.textures.push(Some(img));
+        let texture = self.textures.len() - 1;
+        for (name, region) in atlas.frames {
+            let id = match assets.texture_at(&name) {
+                Some(id) => id,
+                None => continue,
+            };
+            let pivot = assets.texture_entry(id).pivot.unwrap_or(region.pivot);
+            self.regions.insert(
+                id,
+                TextureRegion {
+                    texture,
+                    source: Some(region),
+                    size: Vector::new(region.w, region.h),
+                    pivot: Vector::new(pivot[0], pivot[1]),
+                },
+            );
         }
     }
 
-    pub fn insert(&mut self, path: String, img: HtmlImageElement) {
-        self.textures.push(img);
-        self.names.insert(path, self.textures.len() - 1);
+    pub fn contains(&self, id: TextureId) -> bool {
+        self.regions.contains_key(&id)
+    }
+
+    /// `None` until the texture is loaded.
+    pub fn region(&self, id: TextureId) -> Option<TextureRegion> {
+        self.regions.get(&id).copied()
+    }
+
+    /// Unloads the images `keep` returns false for. Frames of the atlas
+    /// stay, the atlas image is shared by all of them anyway.
+    pub fn retain(&mut self, keep: impl Fn(TextureId) -> bool) {
+        let textures = &mut self.textures;
+        self.regions.retain(|id, region| {
+            if region.source.is_some() || keep(*id) {
+                return true;
+            }
+            textures[region.texture] = None;
+            false
+        });
+    }
+
+    /// Whether the image is still there, renderers drop their copies of
+    /// unloaded ones.
+    pub fn is_loaded(&self, id: usize) -> bool {
+        matches!(self.textures.get(id), Some(Some(_)))
     }
 
-    pub fn get(&self, path: &str) -> &'_ HtmlImageElement {
-        &self.textures[self.names[path]]
+    pub fn by_id(&self, id: usize) -> &'_ HtmlImageElement {
+        self.textures[id]
+            .as_ref()
+            .expect("an unloaded texture was drawn")
     }
 }
@@ -0,0 +1,129 @@
+use web_sys::{Touch, TouchEvent};
+
+use crate::{geometry::Vector, input::Action};
+
+/// How far a finger has to move, in CSS pixels, for a swipe instead of a tap.
+const SWIPE_DISTANCE: f64 = 40.0;
+
+/// What a finger did on the screens without the playfield.
+pub enum Gesture {
+    /// Where the finger was lifted, in CSS pixels of the viewport.
+    Tap(Vector),
+    /// Moves like the d-pad does.
+    Swipe(Action),
+}
+
+/// Relative drag controls: the player moves by as much as the finger moves,
+/// wherever on the canvas the finger is, so it never covers the ship. The
+/// gun fires for as long as the finger stays down. Off the playfield,
+/// fingers make gestures instead.
+pub struct TouchInput {
+    finger: Option<(i32, Vector)>,
+    drag: Vector,
+    /// The finger of a gesture and where it went down.
+    gesture: Option<(i32, Vector)>,
+}
+
+impl TouchInput {
+    pub fn new() -> Self {
+        Self {
+            finger: None,
+            drag: Vector::zero(),
+            gesture: None,
+        }
+    }
+
+    pub fn is_touching(&self) -> bool {
+        self.finger.is_some()
+    }
+
+    pub fn start(&mut self, e: &TouchEvent) {
+        if self.finger.is_some() {
+            return;
+        }
+        if let Some(touch) = e.changed_touches().get(0) {
+            self.finger = Some((touch.identifier(), Self::position(&touch)));
+        }
+    }
+
+    /// `scale` converts from CSS pixels to canvas pixels.
+    pub fn moved(&mut self, e: &TouchEvent, scale: f64) {
+        let (id, last) = match self.finger {
+            Some(finger) => finger,
+            None => return,
+        };
+        if let Some(touch) = Self::find(e, id) {
+            let position = Self::position(&touch);
+            self.drag += (position - last) * scale;
+            self.finger = Some((id, position));
+        }
+    }
+
+    pub fn end(&mut self, e: &TouchEvent) {
+        if let Some((id, _)) = self.finger {
+            if Self::find(e, id).is_some() {
+                self.finger = None;
+            }
+        }
+    }
+
+    /// The browser took the finger away, it neither lifted nor tapped.
+    pub fn cancel(&mut self, e: &TouchEvent) {
+        self.end(e);
+        self.gesture = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.finger = None;
+        self.drag = Vector::zero();
+        self.gesture = None;
+    }
+
+    pub fn gesture_start(&mut self, e: &TouchEvent) {
+        if self.gesture.is_some() {
+            return;
+        }
+        if let Some(touch) = e.changed_touches().get(0) {
+            self.gesture = Some((touch.identifier(), Self::position(&touch)));
+        }
+    }
+
+    /// The gesture finished by the finger being lifted, if it was one.
+    pub fn gesture_end(&mut self, e: &TouchEvent) -> Option<Gesture> {
+        let (id, from) = self.gesture?;
+        let to = Self::position(&Self::find(e, id)?);
+        self.gesture = None;
+        let moved = to - from;
+        if moved.len() < SWIPE_DISTANCE {
+            return Some(Gesture::Tap(to));
+        }
+        let action = if moved.x.abs() > moved.y.abs() {
+            if moved.x < 0.0 {
+                Action::MoveLeft
+            } else {
+                Action::MoveRight
+            }
+        } else if moved.y < 0.0 {
+            Action::MoveUp
+        } else {
+            Action::MoveDown
+        };
+        Some(Gesture::Swipe(action))
+    }
+
+    /// Canvas pixels the finger has moved since the last call.
+    pub fn take_drag(&mut self) -> Vector {
+        std::mem::replace(&mut self.drag, Vector::zero())
+    }
+
+    fn find(e: &TouchEvent, id: i32) -> Option<Touch> {
+        let touches = e.changed_touches();
+        (0..touches.length())
+            .filter_map(|i| touches.get(i))
+            .find(|it| it.identifier() == id)
+    }
+
+    fn position(touch: &Touch) -> Vector {
+        Vector::new(touch.client_x() as f64, touch.client_y() as f64)
+    }
+}
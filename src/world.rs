@@ -1,27 +1,45 @@
-use wasm_bindgen::JsValue;
-use web_sys::{CanvasRenderingContext2d, HtmlImageElement};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    audio::AudioManager,
+    animation::{AnimationSet, Animator, ClipName},
+    assets::{AssetList, Assets, TextureId},
     enemies::Enemy,
+    events::WorldEvent,
     geometry::{Circle, Rect, Vector},
     level::Level,
-    textures::TextureManager,
+    particles::ParticleSystem,
+    rank::Rank,
+    render::{Camera, Color, Frame, Layer, Sprite},
+    textures::{TextureManager, TextureRegion},
 };
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum BulletType {
     PlayerSniper,
     PlayerHeavy,
     Enemy,
 }
 
-#[derive(Clone)]
+/// How a bullet's sprite moves, on top of flying along its speed.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum BulletAnimation {
+    /// Points where it flies.
+    Directional,
+    /// Turns at this many radians per second.
+    Spin(f64),
+    /// Grows and shrinks by `amount` of its size, `rate` times per second.
+    Pulse { rate: f64, amount: f64 },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Bullet {
     pub typ: BulletType,
     pub hitbox: Circle,
     pub speed: Vector,
+    pub animation: BulletAnimation,
+    age: f64,
     marked_for_delete: bool,
+    grazed: bool,
 }
 
 impl Bullet {
@@ -30,19 +48,117 @@ impl Bullet {
             typ,
             hitbox,
             speed,
+            animation: BulletAnimation::Directional,
+            age: 0.0,
             marked_for_delete: false,
+            grazed: false,
         }
     }
+
+    pub fn with_animation(mut self, animation: BulletAnimation) -> Self {
+        self.animation = animation;
+        self
+    }
 }
 
 pub struct World {
     player: Circle,
+    player_animation: Animator,
+    textures: WorldTextures,
+    /// Movement since the last tick, to pick the player's clip by.
+    player_motion: Vector,
     enemies: Vec<Enemy>,
+    /// Killed enemies playing their death clip, they no longer take part.
+    dying: Vec<Enemy>,
+    particles: ParticleSystem,
+    camera: Camera,
+    /// Game time left in slow motion.
+    slow_motion: f64,
     bullets: Vec<Bullet>,
     size: Vector,
+    events: Vec<WorldEvent>,
     pub time: f64,
     pub level: Level,
     pub player_bullets: usize,
+    pub bombs: usize,
+    pub lives: usize,
+    /// Time left after being hit during which bullets pass through.
+    invulnerable: f64,
+    /// Kept from level to level, only a new world starts from zero.
+    pub score: u64,
+    /// Kept from level to level like the score.
+    pub rank: Rank,
+    /// Enemy bullets pass through the player, for debugging.
+    pub invincible: bool,
+    /// Hits don't cost a life, for practice.
+    pub infinite_lives: bool,
+    /// The player moves slowly and its hitbox shows, set from the input
+    /// every tick.
+    pub focused: bool,
+}
+
+/// Everything that decides how the world plays on, so it can be saved and
+/// restored exactly. Particles, the camera and the background are left out,
+/// they only look.
+#[derive(Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    player: Circle,
+    player_animation: Animator,
+    enemies: Vec<Enemy>,
+    dying: Vec<Enemy>,
+    bullets: Vec<Bullet>,
+    scene: usize,
+    time: f64,
+    slow_motion: f64,
+    player_bullets: usize,
+    bombs: usize,
+    lives: usize,
+    invulnerable: f64,
+    score: u64,
+    rank: Rank,
+}
+
+const BOMBS_PER_LEVEL: usize = 3;
+pub const START_LIVES: usize = 3;
+
+/// How long the player can't be hit again after losing a life, and how
+/// many times a second it blinks meanwhile.
+const RESPAWN_INVULNERABILITY: f64 = 2.0;
+const BLINK_RATE: f64 = 10.0;
+
+/// What the world draws whatever the level.
+struct WorldTextures {
+    player: TextureId,
+    enemy_bullet: TextureId,
+    player_bullet: TextureId,
+    hearth: TextureId,
+}
+
+/// Dots along each enemy's path in the debug overlay.
+const DEBUG_PATH_POINTS: usize = 20;
+
+/// How close an enemy bullet has to pass by the player to count as a graze.
+const GRAZE_DISTANCE: f64 = 20.0;
+
+/// Distance between an enemy bullet and the player at which the danger
+/// vignette starts to show.
+const DANGER_DISTANCE: f64 = 40.0;
+
+/// How much time slows down after a boss' last hit, and for how long in
+/// game time.
+const SLOW_MOTION_SCALE: f64 = 0.25;
+const SLOW_MOTION_TIME: f64 = 0.4;
+
+/// What an event is worth to the score.
+fn points(event: &WorldEvent) -> u64 {
+    match event {
+        WorldEvent::EnemyHit { .. } => 10,
+        WorldEvent::BulletCancelled { .. } => 20,
+        WorldEvent::Graze { .. } => 50,
+        WorldEvent::EnemyKilled { .. } => 100,
+        WorldEvent::BossKilled { .. } => 1000,
+        _ => 0,
+    }
 }
 
 #[derive(PartialEq)]
@@ -53,35 +169,185 @@ pub enum TickResult {
 }
 
 impl World {
-    pub fn new(size: Vector, level: Level) -> Self {
+    pub fn new(size: Vector, level: Level, assets: &Assets) -> Self {
+        let textures = WorldTextures {
+            player: assets.texture("green_hearth"),
+            enemy_bullet: assets.texture("missile"),
+            player_bullet: assets.texture("missile_2"),
+            hearth: assets.texture("hearth"),
+        };
         Self {
             player: Circle::new(0.0, size.y / 6.0 * 2.0, 10.0),
+            player_animation: Animator::new(AnimationSet::for_sprite(textures.player)),
+            textures,
+            player_motion: Vector::zero(),
             level,
             enemies: vec![],
+            dying: vec![],
+            particles: ParticleSystem::new(),
+            camera: Camera::new(size),
+            slow_motion: 0.0,
             bullets: vec![],
             size,
+            events: vec![WorldEvent::LevelStarted],
             time: 0.0,
             player_bullets: 1,
+            bombs: BOMBS_PER_LEVEL,
+            lives: START_LIVES,
+            invulnerable: 0.0,
+            score: 0,
+            rank: Rank::default(),
+            invincible: false,
+            infinite_lives: false,
+            focused: false,
         }
     }
 
+    /// Textures every level needs, on top of the level's own.
+    pub fn assets(&self) -> AssetList {
+        AssetList::new(
+            vec![
+                self.textures.player,
+                self.textures.enemy_bullet,
+                self.textures.player_bullet,
+                self.textures.hearth,
+            ],
+            vec![],
+        )
+    }
+
     pub fn move_player(&mut self, delta: Vector) {
         self.player.coord += delta;
+        self.player_motion += delta;
     }
 
     pub fn reset(&mut self, next_level: Level) {
         self.bullets.drain(..);
         self.enemies.drain(..);
+        self.dying.drain(..);
+        self.particles.clear();
+        self.camera.clear();
+        self.slow_motion = 0.0;
+        self.invulnerable = 0.0;
         self.player.coord = Vector::new(0.0, self.size.y / 6.0 * 2.0);
         self.level = next_level;
+        self.bombs = BOMBS_PER_LEVEL;
+        self.events.push(WorldEvent::LevelStarted);
     }
 
-    pub fn tick(&mut self, delta: f64, audio: &AudioManager) -> TickResult {
+    pub fn snapshot(&self) -> WorldSnapshot {
+        WorldSnapshot {
+            player: self.player.clone(),
+            player_animation: self.player_animation.clone(),
+            enemies: self.enemies.clone(),
+            dying: self.dying.clone(),
+            bullets: self.bullets.clone(),
+            scene: self.level.scene(),
+            time: self.time,
+            slow_motion: self.slow_motion,
+            player_bullets: self.player_bullets,
+            bombs: self.bombs,
+            lives: self.lives,
+            invulnerable: self.invulnerable,
+            score: self.score,
+            rank: self.rank,
+        }
+    }
+
+    /// Puts the world back the way it was, the snapshot has to come from the
+    /// same level.
+    pub fn restore(&mut self, snapshot: WorldSnapshot) {
+        self.player = snapshot.player;
+        self.player_animation = snapshot.player_animation;
+        self.enemies = snapshot.enemies;
+        self.dying = snapshot.dying;
+        self.bullets = snapshot.bullets;
+        self.level.restore_scene(snapshot.scene);
+        self.time = snapshot.time;
+        self.slow_motion = snapshot.slow_motion;
+        self.player_bullets = snapshot.player_bullets;
+        self.bombs = snapshot.bombs;
+        self.lives = snapshot.lives;
+        self.invulnerable = snapshot.invulnerable;
+        self.score = snapshot.score;
+        self.rank = snapshot.rank;
+        self.particles.clear();
+        self.camera.clear();
+    }
+
+    /// Drops the enemies on screen so the level starts its next scene.
+    pub fn skip_scene(&mut self) {
+        self.enemies.drain(..);
+    }
+
+    pub fn spawn(&mut self, enemy: Enemy) {
+        self.enemies.push(enemy);
+    }
+
+    pub fn enemies(&self) -> &[Enemy] {
+        &self.enemies
+    }
+
+    pub fn bullet_count(&self) -> usize {
+        self.bullets.len()
+    }
+
+    pub fn take_events(&mut self) -> Vec<WorldEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// `delta` is scaled by `time_scale` already, like the player's
+    /// movement.
+    pub fn tick(&mut self, delta: f64) -> TickResult {
+        self.slow_motion = (self.slow_motion - delta).max(0.0);
+        let result = self.simulate(delta);
+        self.score += self.events.iter().map(points).sum::<u64>();
+        self.rank.handle(&self.events);
+        self.rank.tick(delta, self.player_bullets);
+        self.particles.handle(&self.events);
+        self.camera.handle(&self.events);
+        self.tick_effects(delta);
+        result
+    }
+
+    /// Lets particles and the camera settle while the world itself stands
+    /// still, like under the results of a run.
+    pub fn tick_effects(&mut self, delta: f64) {
+        self.particles.tick(delta);
+        self.camera.tick(delta);
+    }
+
+    /// How fast game time runs compared to real time.
+    pub fn time_scale(&self) -> f64 {
+        if self.slow_motion > 0.0 {
+            SLOW_MOTION_SCALE
+        } else {
+            1.0
+        }
+    }
+
+    fn simulate(&mut self, delta: f64) -> TickResult {
         self.time += delta;
+        self.invulnerable = (self.invulnerable - delta).max(0.0);
 
-        let level_tick = self.level.tick(&mut self.enemies, &mut self.bullets);
-        if level_tick != TickResult::None {
-            return level_tick;
+        let player_speed = if delta > 0.0 {
+            self.player_motion.x / delta
+        } else {
+            0.0
+        };
+        self.player_motion = Vector::zero();
+        self.player_animation.tick(delta, player_speed);
+        let scroll_speed = self.level.scroll_speed();
+        self.level.background.tick(delta, scroll_speed);
+
+        // Nothing new starts while a boss' death plays out in slow motion.
+        if self.slow_motion <= 0.0 {
+            let level_tick =
+                self.level
+                    .tick(&mut self.enemies, &mut self.bullets, &mut self.events);
+            if level_tick != TickResult::None {
+                return level_tick;
+            }
         }
 
         // === Collisions ===
@@ -99,13 +365,51 @@ impl World {
             self.player.coord.y = self.size.y / 2.0 - self.player.r;
         }
 
+        let graze_zone = Circle {
+            coord: self.player.coord,
+            r: self.player.r + GRAZE_DISTANCE,
+        };
+        let mut closest = f64::INFINITY;
+        let can_be_hit = !self.invincible && self.invulnerable <= 0.0;
+        let mut hit = false;
         for bullet in self.bullets.iter_mut() {
-            if self.player.collides_with(&bullet.hitbox) && bullet.typ == BulletType::Enemy {
+            if bullet.typ != BulletType::Enemy {
+                continue;
+            }
+            let distance =
+                (bullet.hitbox.coord - self.player.coord).len() - bullet.hitbox.r - self.player.r;
+            closest = closest.min(distance);
+            if can_be_hit && !hit && self.player.collides_with(&bullet.hitbox) {
+                hit = true;
+                bullet.marked_for_delete = true;
+                continue;
+            }
+            if !bullet.grazed && graze_zone.collides_with(&bullet.hitbox) {
+                bullet.grazed = true;
+                self.events.push(WorldEvent::Graze {
+                    position: bullet.hitbox.coord,
+                });
+            }
+        }
+
+        if hit {
+            self.events.push(WorldEvent::PlayerHit {
+                position: self.player.coord,
+            });
+            self.player_animation.trigger(ClipName::Hurt);
+            if !self.infinite_lives {
+                self.lives = self.lives.saturating_sub(1);
+            }
+            if self.lives == 0 {
+                self.events.push(WorldEvent::PlayerDied);
                 return TickResult::Loose;
             }
+            self.invulnerable = RESPAWN_INVULNERABILITY;
         }
 
-        let mut bullet_collision = false;
+        self.camera
+            .set_danger(1.0 - closest.max(0.0) / DANGER_DISTANCE);
+
         for i in 0..self.bullets.len() {
             let bi = self.bullets[i].clone();
             for j in (i + 1)..self.bullets.len() {
@@ -116,41 +420,51 @@ impl World {
                 if bi.hitbox.collides_with(&bj.hitbox) && bi.typ != bj.typ {
                     self.bullets[i].marked_for_delete = true;
                     self.bullets[j].marked_for_delete = true;
-                    bullet_collision = true;
+                    let enemy_bullet = if bi.typ == BulletType::Enemy {
+                        &bi
+                    } else {
+                        &bj
+                    };
+                    self.events.push(WorldEvent::BulletCancelled {
+                        position: enemy_bullet.hitbox.coord,
+                    });
                 }
             }
         }
-        if bullet_collision {
-            audio.play_name("resources/shoot.wav", false, true, 0.3);
-        }
 
-        let mut hit_enemy = false;
         for e in self.enemies.iter_mut() {
             for bullet in self.bullets.iter_mut() {
                 if e.hitbox().collides_with(&bullet.hitbox) {
+                    let was_alive = e.is_alive();
                     if bullet.typ == BulletType::PlayerSniper {
                         e.hit(3.0);
                         bullet.marked_for_delete = true;
-                        hit_enemy = true;
                     } else if bullet.typ == BulletType::PlayerHeavy {
                         bullet.marked_for_delete = true;
-                        hit_enemy = true;
+                    } else {
+                        continue;
+                    }
+                    self.events.push(WorldEvent::EnemyHit {
+                        position: bullet.hitbox.coord,
+                    });
+                    if was_alive && !e.is_alive() {
+                        self.events.push(WorldEvent::EnemyKilled {
+                            position: e.hitbox().coord,
+                        });
                     }
                 }
             }
         }
-        if hit_enemy {
-            audio.play_name("resources/shoot_2.wav", false, true, 0.3);
-        }
 
         // === Delete enemies and bullets ===
 
         for enemy in self.enemies.iter_mut() {
-            enemy.tick(delta, &mut self.bullets);
+            enemy.tick(delta, self.rank, &mut self.bullets, &mut self.events);
         }
 
         for bullet in self.bullets.iter_mut() {
             bullet.hitbox.coord += bullet.speed * delta;
+            bullet.age += delta;
             if !bullet.hitbox.in_bounds(
                 -self.size.x / 2.0,
                 -self.size.y / 2.0,
@@ -163,7 +477,21 @@ impl World {
 
         self.bullets.retain(|it| !it.marked_for_delete);
 
-        self.enemies.retain(|it| it.is_alive());
+        for enemy in self.dying.iter_mut() {
+            enemy.animation.tick(delta, 0.0);
+        }
+        self.dying.retain(|it| !it.animation.is_dead());
+        let (alive, dead): (Vec<_>, Vec<_>) = self.enemies.drain(..).partition(|it| it.is_alive());
+        self.enemies = alive;
+        if self.enemies.is_empty() && self.level.is_boss_scene() {
+            if let Some(boss) = dead.last() {
+                self.events.push(WorldEvent::BossKilled {
+                    position: boss.hitbox().coord,
+                });
+                self.slow_motion = SLOW_MOTION_TIME;
+            }
+        }
+        self.dying.extend(dead);
 
         TickResult::None
     }
@@ -172,151 +500,245 @@ impl World {
         Bullet::new(BulletType::Enemy, Circle::new(coord.x, coord.y, 5.0), speed)
     }
 
-    pub fn shoot(&mut self, speed: Vector, typ: BulletType, audio: &AudioManager) {
+    pub fn shoot(&mut self, speed: Vector, typ: BulletType) {
         let r = match &typ {
             BulletType::PlayerSniper => 5.0,
             BulletType::PlayerHeavy => 10.0,
             BulletType::Enemy => 5.0,
         };
         let mut left = -(r + 1.0) * (self.player_bullets - 1) as f64 / 2.0;
+        let animation = match typ {
+            BulletType::PlayerHeavy => BulletAnimation::Pulse {
+                rate: 4.0,
+                amount: 0.15,
+            },
+            _ => BulletAnimation::Directional,
+        };
         for _ in 0..self.player_bullets {
-            self.bullets.push(Bullet::new(
-                typ,
-                Circle::new(self.player.coord.x + left, self.player.coord.y, r),
-                speed,
-            ));
+            self.bullets.push(
+                Bullet::new(
+                    typ,
+                    Circle::new(self.player.coord.x + left, self.player.coord.y, r),
+                    speed,
+                )
+                .with_animation(animation),
+            );
             left += r + 1.0;
         }
-        audio.play_name("resources/shoot_3.wav", false, true, 0.1);
+        self.events.push(WorldEvent::PlayerShot {
+            position: self.player.coord,
+        });
     }
 
-    pub fn draw(&self, context: &CanvasRenderingContext2d, texture_manager: &TextureManager) {
-        let missile = texture_manager.get("resources/missile.png");
-        let missile_2 = texture_manager.get("resources/missile_2.png");
-        let hearth = texture_manager.get("resources/hearth.png");
-        let green_hearth = texture_manager.get("resources/green_hearth.png");
-
-        context.save();
-        context.set_global_composite_operation("copy").unwrap();
-        context.set_fill_style(&JsValue::from_str("rgba(0,0,0,0)"));
-        context.fill_rect(0.0, 0.0, 700.0, 1100.0);
-        context.restore();
-
-        self.draw_back(context, texture_manager);
-
-        let player_bounds = Rect::new(
-            self.player.coord.x,
-            self.player.coord.y,
-            green_hearth.width() as f64,
-            green_hearth.height() as f64,
-        )
-        .with_width(self.player.r * 3.5);
+    /// Cancels every enemy bullet on screen, if there are bombs left.
+    pub fn bomb(&mut self) {
+        if self.bombs == 0 {
+            return;
+        }
+        self.bombs -= 1;
+        self.events.push(WorldEvent::BombUsed {
+            position: self.player.coord,
+        });
+        for bullet in self.bullets.iter() {
+            if bullet.typ == BulletType::Enemy {
+                self.events.push(WorldEvent::BulletCancelled {
+                    position: bullet.hitbox.coord,
+                });
+            }
+        }
+        self.bullets.retain(|it| it.typ != BulletType::Enemy);
+    }
 
-        // self.draw_circle(context, &self.player, "gray");
-        self.draw_image(context, &player_bounds, green_hearth);
+    pub fn draw(&self, frame: &mut Frame, texture_manager: &TextureManager) {
+        let enemy_bullet = texture_manager.region(self.textures.enemy_bullet);
+        let player_bullet = texture_manager.region(self.textures.player_bullet);
+        let hearth = texture_manager.region(self.textures.hearth);
+
+        self.draw_back(frame, texture_manager);
+
+        if ((self.invulnerable * BLINK_RATE) as u64).is_multiple_of(2) {
+            self.draw_animated(
+                frame,
+                texture_manager,
+                &self.player_animation,
+                self.player.coord,
+                self.player.r * 3.5,
+            );
+        }
+        if self.focused {
+            self.draw_circle(frame, &self.player, Color::WHITE);
+        }
 
+        for enemy in self.dying.iter() {
+            self.draw_animated(
+                frame,
+                texture_manager,
+                &enemy.animation,
+                enemy.hitbox().coord,
+                enemy.display_width,
+            );
+        }
         for enemy in self.enemies.iter() {
-            let img = texture_manager.get(&enemy.sprite);
             let center = enemy.hitbox().coord;
-            let w = img.width() as f64;
-            let h = img.height() as f64;
-
-            let bounds = Rect::new(center.x, center.y, w, h).with_width(enemy.display_width);
-            let hearth_bounds = Rect::new(
-                center.x,
-                center.y,
-                hearth.width() as f64,
-                hearth.height() as f64,
-            )
-            .with_width(enemy.hitbox().r * 3.0);
-
-            self.draw_image(context, &bounds, img);
-            // self.draw_circle(context, enemy.hitbox(), "purple");
-            self.draw_image(context, &hearth_bounds, hearth);
+            self.draw_animated(
+                frame,
+                texture_manager,
+                &enemy.animation,
+                center,
+                enemy.display_width,
+            );
+            if let Some(hearth) = &hearth {
+                let hearth_bounds = Rect::new(center.x, center.y, hearth.size.x, hearth.size.y)
+                    .with_width(enemy.hitbox().r * 3.0);
+                self.draw_image(frame, &hearth_bounds, hearth);
+            }
         }
         for bullet in self.bullets.iter() {
             match bullet.typ {
                 BulletType::PlayerSniper => {
-                    self.draw_bullet(context, missile_2, bullet, 1.5);
+                    if let Some(img) = &player_bullet {
+                        self.draw_bullet(frame, img, bullet, 1.5);
+                    }
                 }
                 BulletType::Enemy => {
-                    self.draw_bullet(context, missile, bullet, 1.5);
+                    if let Some(img) = &enemy_bullet {
+                        self.draw_bullet(frame, img, bullet, 1.5);
+                    }
+                }
+                BulletType::PlayerHeavy => {
+                    let mut circle = bullet.hitbox.clone();
+                    circle.r *= Self::bullet_scale(bullet);
+                    self.draw_circle(frame, &circle, Color::CYAN)
                 }
-                BulletType::PlayerHeavy => self.draw_circle(context, &bullet.hitbox, "cyan"),
             }
         }
+
+        self.particles.draw(frame, self.to_canvas(Vector::zero()));
+
+        for enemy in self.enemies.iter() {
+            self.draw_health_bar(frame, enemy);
+        }
+
+        self.camera.draw(frame);
     }
 
-    fn draw_back(&self, context: &CanvasRenderingContext2d, texture_manager: &TextureManager) {
-        let img = texture_manager.get(&self.level.background);
-        
-        let t = (self.time * 50.0) % 250.0;
-        for i in -3..4 {
-            self.draw_image(context, &Rect::new(0.0, 249.0 * i as f64 + t, 600.0, 350.0), img)
+    /// Hitboxes, where enemies are headed for the rest of their phase and
+    /// where their bullets come from.
+    pub fn draw_debug(&self, frame: &mut Frame) {
+        for enemy in self.enemies.iter() {
+            for point in enemy.path_ahead(DEBUG_PATH_POINTS) {
+                frame.circle(
+                    Layer::World,
+                    self.to_canvas(point),
+                    2.0,
+                    Color::rgba(255, 255, 255, 0.6),
+                );
+            }
+            self.draw_circle(frame, enemy.hitbox(), Color::rgba(128, 0, 128, 0.5));
+            for origin in enemy.emitter_origins() {
+                frame.circle(Layer::World, self.to_canvas(origin), 3.0, Color::YELLOW);
+            }
         }
+        for bullet in self.bullets.iter() {
+            self.draw_circle(frame, &bullet.hitbox, Color::rgba(255, 0, 0, 0.5));
+        }
+        self.draw_circle(frame, &self.player, Color::rgba(128, 128, 128, 0.7));
     }
 
-    fn draw_circle(&self, context: &CanvasRenderingContext2d, circle: &Circle, color: &str) {
-        context.begin_path();
-        context.set_fill_style(&JsValue::from_str(color));
-        context
-            .arc(
-                self.size.x / 2.0 + circle.coord.x,
-                self.size.y / 2.0 + circle.coord.y,
-                circle.r,
-                0.0,
-                std::f64::consts::PI * 2.0,
-            )
-            .unwrap();
-        context.fill();
-        context.close_path();
+    fn draw_health_bar(&self, frame: &mut Frame, enemy: &Enemy) {
+        let width = enemy.display_width * 0.8;
+        let top_left = self.to_canvas(
+            enemy.hitbox().coord - Vector::new(width / 2.0, enemy.display_width / 2.0 + 12.0),
+        );
+        frame.rect(
+            Layer::World,
+            top_left,
+            Vector::new(width, 6.0),
+            Color::rgba(0, 0, 0, 0.5),
+        );
+        frame.rect(
+            Layer::World,
+            top_left,
+            Vector::new(width * enemy.health_fraction(), 6.0),
+            Color::rgb(255, 60, 60),
+        );
     }
 
-    fn draw_image(
+    /// Draws the current frame of `animation`, `width` wide at `center`.
+    fn draw_animated(
         &self,
-        context: &CanvasRenderingContext2d,
-        bounds: &Rect,
-        img: &HtmlImageElement,
+        frame: &mut Frame,
+        texture_manager: &TextureManager,
+        animation: &Animator,
+        center: Vector,
+        width: f64,
     ) {
-        context
-            .draw_image_with_html_image_element_and_dw_and_dh(
-                img,
-                self.size.x / 2.0 + bounds.center.x - bounds.size.x / 2.0,
-                self.size.y / 2.0 + bounds.center.y - bounds.size.y / 2.0,
-                bounds.size.x,
-                bounds.size.y,
+        let current = animation.frame();
+        let img = match texture_manager.region(current.texture) {
+            Some(img) => img,
+            None => return,
+        };
+        let bounds = Rect::new(center.x, center.y, img.size.x, img.size.y).with_width(width);
+        frame.sprite(
+            Layer::World,
+            Sprite::new(
+                &img,
+                self.to_canvas(bounds.center),
+                bounds.size * current.scale,
             )
-            .unwrap();
+            .with_angle(current.angle)
+            .with_alpha(current.alpha),
+        );
     }
 
-    fn draw_bullet(
-        &self,
-        context: &CanvasRenderingContext2d,
-        img: &HtmlImageElement,
-        bullet: &Bullet,
-        size_mod: f64,
-    ) {
-        let mut circle = bullet.hitbox.clone();
-        circle.r *= size_mod;
-        let angle = bullet.speed.norm().angle() - std::f64::consts::PI / 2.0;
-
-        context.save();
-        context
-            .translate(
-                self.size.x / 2.0 + circle.coord.x,
-                self.size.y / 2.0 + circle.coord.y,
-            )
-            .unwrap();
-        context.rotate(angle).unwrap();
-        context
-            .draw_image_with_html_image_element_and_dw_and_dh(
+    fn draw_back(&self, frame: &mut Frame, texture_manager: &TextureManager) {
+        self.level.background.draw(
+            frame,
+            texture_manager,
+            self.to_canvas(self.size * -0.5),
+            self.size,
+        );
+    }
+
+    fn draw_circle(&self, frame: &mut Frame, circle: &Circle, color: Color) {
+        frame.circle(Layer::World, self.to_canvas(circle.coord), circle.r, color);
+    }
+
+    fn draw_image(&self, frame: &mut Frame, bounds: &Rect, img: &TextureRegion) {
+        frame.sprite(
+            Layer::World,
+            Sprite::new(img, self.to_canvas(bounds.center), bounds.size),
+        );
+    }
+
+    fn draw_bullet(&self, frame: &mut Frame, img: &TextureRegion, bullet: &Bullet, size_mod: f64) {
+        let r = bullet.hitbox.r * size_mod * Self::bullet_scale(bullet);
+        let angle = match bullet.animation {
+            BulletAnimation::Spin(speed) => bullet.age * speed,
+            _ => bullet.speed.norm().angle() - std::f64::consts::PI / 2.0,
+        };
+
+        frame.sprite(
+            Layer::World,
+            Sprite::new(
                 img,
-                -circle.r,
-                -circle.r,
-                circle.r * 2.0,
-                circle.r * 2.0,
+                self.to_canvas(bullet.hitbox.coord),
+                Vector::new(r * 2.0, r * 2.0),
             )
-            .unwrap();
-        context.restore();
+            .with_angle(angle),
+        );
+    }
+
+    fn bullet_scale(bullet: &Bullet) -> f64 {
+        match bullet.animation {
+            BulletAnimation::Pulse { rate, amount } => {
+                1.0 + amount * (bullet.age * rate * std::f64::consts::TAU).sin()
+            }
+            _ => 1.0,
+        }
+    }
+
+    fn to_canvas(&self, coord: Vector) -> Vector {
+        self.camera.to_canvas(coord)
     }
 }
@@ -0,0 +1,90 @@
+// Caches the game so it can be played offline. The deploy workflow replaces
+// CACHE_VERSION with the commit being deployed, a new version downloads
+// everything again and deletes the old cache once it takes over.
+const CACHE_VERSION = "dev";
+const CACHE = `tohou-${CACHE_VERSION}`;
+
+// Every asset is listed here, the atlas only exists in deployed builds.
+const ASSET_MANIFEST = "resources/assets.json";
+const OPTIONAL = ["resources/atlas.json", "resources/atlas.png"];
+// The install icons, kept out of resources/ itself so they aren't packed.
+const ICONS = ["resources/icons/hearth-192.png", "resources/icons/hearth-512.png"];
+
+self.addEventListener("install", (event) => {
+    event.waitUntil(precache().then(() => self.skipWaiting()));
+});
+
+self.addEventListener("activate", (event) => {
+    event.waitUntil(
+        caches
+            .keys()
+            .then((keys) =>
+                Promise.all(keys.filter((key) => key !== CACHE).map((key) => caches.delete(key)))
+            )
+            .then(() => self.clients.claim())
+    );
+});
+
+self.addEventListener("fetch", (event) => {
+    if (event.request.method !== "GET") {
+        return;
+    }
+    if (event.request.mode === "navigate") {
+        event.respondWith(networkFirst(event.request, "./"));
+    } else if (CACHE_VERSION === "dev") {
+        // Local builds change without the version changing.
+        event.respondWith(networkFirst(event.request, event.request));
+    } else {
+        event.respondWith(cacheFirst(event.request));
+    }
+});
+
+async function precache() {
+    const cache = await caches.open(CACHE);
+
+    // trunk names the wasm and its JS glue after their hash, they are found
+    // in the page that loads them.
+    const page = await fetch("./", { cache: "no-cache" });
+    const html = await page.clone().text();
+    await cache.put("./", page);
+    const bundle = [...html.matchAll(/(?:href|src)="([^"]+\.(?:wasm|js|css))"/g)].map(
+        (match) => match[1]
+    );
+
+    const manifest = await (await fetch(ASSET_MANIFEST, { cache: "no-cache" })).json();
+    const assets = manifest.assets.map((asset) => asset.path);
+    await cache.addAll([...bundle, ASSET_MANIFEST, "manifest.webmanifest", ...ICONS, ...assets]);
+
+    await Promise.all(OPTIONAL.map((path) => cache.add(path).catch(() => {})));
+}
+
+// The page is always fetched when online, so a new deploy is noticed.
+async function networkFirst(request, key) {
+    const cache = await caches.open(CACHE);
+    try {
+        const response = await fetch(request);
+        if (response.ok) {
+            await cache.put(key, response.clone());
+        }
+        return response;
+    } catch (error) {
+        const cached = await cache.match(key, { ignoreSearch: true });
+        if (cached) {
+            return cached;
+        }
+        throw error;
+    }
+}
+
+async function cacheFirst(request) {
+    const cache = await caches.open(CACHE);
+    const cached = await cache.match(request, { ignoreSearch: true });
+    if (cached) {
+        return cached;
+    }
+    const response = await fetch(request);
+    if (response.ok && new URL(request.url).origin === self.location.origin) {
+        await cache.put(request, response.clone());
+    }
+    return response;
+}
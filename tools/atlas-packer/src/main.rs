@@ -0,0 +1,219 @@
+//! Packs every png in a directory into `atlas.png` and describes where each
+//! one ended up in `atlas.json`, in the format the game reads in `atlas.rs`.
+//!
+//! Usage: atlas-packer <dir> [--grid <file>=<columns>x<rows>]...
+//!
+//! A file given with `--grid` is a sprite sheet, its cells are added as
+//! frames `<name>#0`, `<name>#1`, ... row by row, next to the whole image.
+
+use std::{collections::BTreeMap, env, fs, path::Path, process};
+
+use image::RgbaImage;
+use serde::Serialize;
+
+const ATLAS_NAME: &str = "atlas";
+/// Space between images. Edge pixels are repeated into it so linear
+/// filtering never blends in a neighbour.
+const PADDING: u32 = 2;
+const MAX_SIZE: u32 = 4096;
+
+#[derive(Serialize)]
+struct Region {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Serialize)]
+struct Atlas {
+    image: String,
+    frames: BTreeMap<String, Region>,
+}
+
+struct Input {
+    name: String,
+    image: RgbaImage,
+    grid: Option<(u32, u32)>,
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("atlas-packer: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut args = env::args().skip(1);
+    let dir = args
+        .next()
+        .ok_or("usage: atlas-packer <dir> [--grid <file>=<columns>x<rows>]...")?;
+    let mut grids = BTreeMap::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--grid" => {
+                let value = args.next().ok_or("--grid needs a value")?;
+                let (file, grid) = parse_grid(&value)?;
+                grids.insert(file, grid);
+            }
+            _ => return Err(format!("unknown argument {}", arg)),
+        }
+    }
+
+    let dir = Path::new(&dir);
+    // Names are the paths the game requests, relative to the site root.
+    let prefix = dir
+        .file_name()
+        .and_then(|it| it.to_str())
+        .ok_or("bad directory name")?;
+    let mut inputs = read_inputs(dir, prefix, &grids)?;
+    inputs.sort_by(|a, b| b.image.height().cmp(&a.image.height()));
+
+    let (width, height, positions) = pack(&inputs)?;
+    let mut atlas_image = RgbaImage::new(width, height);
+    let mut frames = BTreeMap::new();
+    for (input, (x, y)) in inputs.iter().zip(positions) {
+        blit(&mut atlas_image, &input.image, x, y);
+        let (w, h) = input.image.dimensions();
+        frames.insert(input.name.clone(), Region { x, y, w, h });
+
+        if let Some((columns, rows)) = input.grid {
+            let (cell_w, cell_h) = (w / columns, h / rows);
+            for i in 0..columns * rows {
+                frames.insert(
+                    format!("{}#{}", input.name, i),
+                    Region {
+                        x: x + (i % columns) * cell_w,
+                        y: y + (i / columns) * cell_h,
+                        w: cell_w,
+                        h: cell_h,
+                    },
+                );
+            }
+        }
+    }
+
+    let image_path = dir.join(format!("{}.png", ATLAS_NAME));
+    atlas_image
+        .save(&image_path)
+        .map_err(|e| format!("failed to write {}: {}", image_path.display(), e))?;
+    let atlas = Atlas {
+        image: format!("{}/{}.png", prefix, ATLAS_NAME),
+        frames,
+    };
+    let json = serde_json::to_string_pretty(&atlas).map_err(|e| e.to_string())?;
+    let json_path = dir.join(format!("{}.json", ATLAS_NAME));
+    fs::write(&json_path, json)
+        .map_err(|e| format!("failed to write {}: {}", json_path.display(), e))?;
+
+    println!(
+        "Packed {} images into {}x{} {}",
+        inputs.len(),
+        width,
+        height,
+        image_path.display()
+    );
+    Ok(())
+}
+
+fn parse_grid(value: &str) -> Result<(String, (u32, u32)), String> {
+    let error = || format!("bad grid {}, expected <file>=<columns>x<rows>", value);
+    let (file, grid) = value.split_once('=').ok_or_else(error)?;
+    let (columns, rows) = grid.split_once('x').ok_or_else(error)?;
+    let columns = columns.parse::<u32>().map_err(|_| error())?;
+    let rows = rows.parse::<u32>().map_err(|_| error())?;
+    if columns == 0 || rows == 0 {
+        return Err(error());
+    }
+    Ok((file.to_string(), (columns, rows)))
+}
+
+fn read_inputs(
+    dir: &Path,
+    prefix: &str,
+    grids: &BTreeMap<String, (u32, u32)>,
+) -> Result<Vec<Input>, String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("failed to read {}: {}", dir.display(), e))?;
+    let mut inputs = vec![];
+    for entry in entries {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        let file = match path.file_name().and_then(|it| it.to_str()) {
+            Some(file) => file.to_string(),
+            None => continue,
+        };
+        if !file.ends_with(".png") || file == format!("{}.png", ATLAS_NAME) {
+            continue;
+        }
+        let image = image::open(&path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?
+            .to_rgba8();
+        if image.width() == 0 || image.height() == 0 {
+            continue;
+        }
+        inputs.push(Input {
+            name: format!("{}/{}", prefix, file),
+            grid: grids.get(&file).copied(),
+            image,
+        });
+    }
+    for file in grids.keys() {
+        if !inputs
+            .iter()
+            .any(|it| it.name.ends_with(&format!("/{}", file)))
+        {
+            return Err(format!(
+                "--grid names {}, which is not in the directory",
+                file
+            ));
+        }
+    }
+    Ok(inputs)
+}
+
+/// Shelf packing of images sorted by height, trying wider atlases until the
+/// result is no taller than it is wide.
+fn pack(inputs: &[Input]) -> Result<(u32, u32, Vec<(u32, u32)>), String> {
+    let widest = inputs.iter().map(|it| it.image.width()).max().unwrap_or(1) + PADDING * 2;
+    let mut width = widest.next_power_of_two();
+    loop {
+        let (height, positions) = pack_into(inputs, width);
+        if height <= width || width >= MAX_SIZE {
+            if height > MAX_SIZE {
+                return Err(format!("images do not fit into {}x{}", MAX_SIZE, MAX_SIZE));
+            }
+            return Ok((width, height, positions));
+        }
+        width *= 2;
+    }
+}
+
+fn pack_into(inputs: &[Input], width: u32) -> (u32, Vec<(u32, u32)>) {
+    let mut positions = vec![];
+    let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+    for input in inputs {
+        let (w, h) = input.image.dimensions();
+        let (w, h) = (w + PADDING * 2, h + PADDING * 2);
+        if x + w > width {
+            x = 0;
+            y += shelf_height;
+            shelf_height = 0;
+        }
+        positions.push((x + PADDING, y + PADDING));
+        x += w;
+        shelf_height = shelf_height.max(h);
+    }
+    (y + shelf_height, positions)
+}
+
+fn blit(atlas: &mut RgbaImage, image: &RgbaImage, x: u32, y: u32) {
+    let (w, h) = image.dimensions();
+    for dy in 0..h + PADDING * 2 {
+        for dx in 0..w + PADDING * 2 {
+            let sx = dx.saturating_sub(PADDING).min(w - 1);
+            let sy = dy.saturating_sub(PADDING).min(h - 1);
+            atlas.put_pixel(x + dx - PADDING, y + dy - PADDING, *image.get_pixel(sx, sy));
+        }
+    }
+}